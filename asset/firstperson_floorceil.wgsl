@@ -34,6 +34,7 @@ struct PixelInfo {
 @group(0) @binding(0) var<uniform> surface: SurfaceInfo;
 @group(0) @binding(1) var<uniform> camera: CameraInfo;
 @group(0) @binding(2) var<storage, read> tilemap: TileMapInfo;
+@group(0) @binding(3) var<storage, read> texture_frames: array<i32>; // texture id -> current frame of the animated texture
 
 @group(1) @binding(0) var<storage, read_write> scanlines: array<ScanlineData>; // intermediate result
 @group(1) @binding(1) var<storage, read_write> pixels: array<PixelInfo>; // final result
@@ -81,10 +82,18 @@ fn pixproc(w: u32, h: u32) {
 		pixels[i].texid = vec2<i32>(-1, -1);
 	}
 	else {
-		pixels[i].texid = vec2<i32>(tilemap.tile_texids[tile_coord.y * tilemap.size.x + tile_coord.x]);
+		let texids = tilemap.tile_texids[tile_coord.y * tilemap.size.x + tile_coord.x];
+		pixels[i].texid = vec2<i32>(animated_texid(texids.x), animated_texid(texids.y));
 	}
 }
 
+fn animated_texid(texid: i32) -> i32 {
+	if texid < 0 || u32(texid) >= arrayLength(&texture_frames) {
+		return texid;
+	}
+	return texture_frames[texid];
+}
+
 struct FragmentOutput {
 	@location(0) color: vec4<f32>,
 	@builtin(frag_depth) depth: f32
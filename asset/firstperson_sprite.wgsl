@@ -0,0 +1,99 @@
+struct SurfaceInfo {
+	width: u32,
+	height: u32
+}
+
+struct CameraInfo {
+	tilepos: vec2<f32>,
+	dirvec: vec2<f32>,
+	plane: vec2<f32>,
+	near: f32,
+	far: f32
+}
+
+struct RaycastData {
+	distance: f32,
+	depth: f32,
+	texid: i32,
+	u_offset: f32,
+	lock: u32, // 0: not locked, key id + 1
+	side: u32 // 0: face perpendicular to x axis, 1: y axis
+}
+
+struct RaycastDataArray {
+	raycount: u32,
+	data: array<RaycastData>
+}
+
+@group(0) @binding(0) var<uniform> surface: SurfaceInfo;
+@group(0) @binding(1) var<uniform> camera: CameraInfo;
+@group(0) @binding(2) var<storage, read> raycast_data_array: RaycastDataArray;
+
+@group(1) @binding(0) var sprite_texture_array: texture_2d_array<f32>;
+@group(1) @binding(1) var texture_sampler: sampler;
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+	@location(1) @interpolate(flat) texid: u32,
+	@location(2) @interpolate(flat) depth: f32
+}
+
+// Billboard quad of one tile size standing at the sprite position(tile space).
+@vertex
+fn vs_main(
+	@builtin(vertex_index) idx: u32,
+	@location(0) position: vec2<f32>,
+	@location(1) texid: u32
+) -> VertexOutput {
+	var out: VertexOutput;
+
+	// Same ray parameterization as the wall compute pass: rel = distance * (dirvec + plane * (0.5 - x_ratio))
+	let rel = position - camera.tilepos;
+	let distance = dot(rel, camera.dirvec) / dot(camera.dirvec, camera.dirvec);
+	if distance <= 0.01 { // Behind the camera. Emit degenerate triangle.
+		out.clip_position = vec4<f32>(0.0, 0.0, 2.0, 1.0);
+		return out;
+	}
+	let lateral = dot(rel, camera.plane) / dot(camera.plane, camera.plane);
+	let half_width = 0.5 / length(camera.plane);
+
+	// 0: right bottom, 1: right top, 2: left bottom, 3: left top
+	let right = idx < 2u;
+	let top = idx % 2u == 1u;
+	let x_ratio = 0.5 - (lateral + select(half_width, -half_width, right)) / distance;
+
+	//HACK: Same wall height factor(2.5) as firstperson_wall_frag.
+	let half_height = 2.5 / distance;
+
+	out.clip_position = vec4<f32>(x_ratio * 2.0 - 1.0, select(-half_height, half_height, top), 0.0, 1.0);
+	out.uv = vec2<f32>(select(0.0, 1.0, right), select(1.0, 0.0, top));
+	out.texid = texid;
+	out.depth = (distance - camera.near) / (camera.far - camera.near);
+	return out;
+}
+
+struct FragmentOutput {
+	@location(0) color: vec4<f32>,
+	@builtin(frag_depth) depth: f32
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+	let color = textureSample(sprite_texture_array, texture_sampler, in.uv, in.texid);
+
+	// Per column occlusion by the wall raycasting result.
+	let raycount = raycast_data_array.raycount;
+	let index = min(u32(f32(i32(raycount) - 1) * in.clip_position.x / f32(surface.width)), raycount - 1u);
+	if in.depth >= raycast_data_array.data[index].depth {
+		discard;
+	}
+	if color.a < 0.5 {
+		discard;
+	}
+
+	var out: FragmentOutput;
+	out.color = color;
+	out.depth = in.depth; // Floor and ceiling occlusion by depth test.
+	return out;
+}
@@ -12,16 +12,31 @@ struct CameraInfo {
 	far: f32
 }
 
+struct Tile {
+	texids: array<i32, 4>, // north(-y), south, east(+x), west faces. If texids[0]!=-1(= if tile has texture), this tile is solid(wall or door).
+	kind: u32,
+	offset: f32 // door: open amount, push wall: block offset from the tile along moving axis, gate: key id
+}
+
+const TILE_WALL: u32 = 0u;
+const TILE_DOOR_VERTICAL: u32 = 1u;
+const TILE_DOOR_HORIZONTAL: u32 = 2u;
+const TILE_PUSHWALL_X: u32 = 3u;
+const TILE_PUSHWALL_Y: u32 = 4u;
+const TILE_GATE: u32 = 5u;
+
 struct TileMapInfo {
 	size: vec2<u32>, // x=width, y=height. Also used for out_of_bound.
-	tile_texids: array<i32> // if texid!=-1(= if tile has texture), this tile is solid(wall).
+	tiles: array<Tile>
 }
 
 struct RaycastData {
 	distance: f32,
 	depth: f32,
 	texid: i32,
-	u_offset: f32
+	u_offset: f32,
+	lock: u32, // 0: not locked, key id + 1
+	side: u32 // 0: face perpendicular to x axis, 1: y axis
 }
 
 struct RaycastDataArray {
@@ -34,12 +49,22 @@ struct RaycastDataArray {
 @group(0) @binding(2) var<storage, read> tilemap: TileMapInfo;
 
 @group(0) @binding(3) var<storage, read_write> raydata: RaycastDataArray;
+@group(0) @binding(4) var<storage, read> texture_frames: array<i32>; // texture id -> current frame of the animated texture
 
 // Get vector of ray by gid and do single raycasting per compute unit.
 @compute @workgroup_size(1)
 fn multiraycast(@builtin(global_invocation_id) gid: vec3<u32>) {
 	let rayvec = camera.dirvec + camera.plane * (0.5 - f32(gid.x) / f32(raydata.raycount));
-	raydata.data[gid.x] = raycast(rayvec);
+	var result = raycast(rayvec);
+	result.texid = animated_texid(result.texid);
+	raydata.data[gid.x] = result;
+}
+
+fn animated_texid(texid: i32) -> i32 {
+	if texid < 0 || u32(texid) >= arrayLength(&texture_frames) {
+		return texid;
+	}
+	return texture_frames[texid];
 }
 
 fn raycast(rayvec: vec2<f32>) -> RaycastData {
@@ -69,6 +94,17 @@ fn raycast(rayvec: vec2<f32>) -> RaycastData {
 	var tile_coord = vec2<i32>(camera.tilepos);
 	var side = 0;
 
+	// Door or push wall can be in the tile where the camera is.
+	if !out_of_bound(tile_coord) {
+		let tile = tilemap.tiles[u32(tile_coord.y * i32(tilemap.size.x) + tile_coord.x)];
+		if tile.texids[0] != -1 && is_tile_object(tile) {
+			let result = raycast_tile_object(rayvec, tile, tile_coord, 0.0, min(side_dist.x, side_dist.y));
+			if result.texid != -1 {
+				return result;
+			}
+		}
+	}
+
 	// While the ray is not out of bound(The ray is not out of edge of the map)...
 	while !out_of_bound(tile_coord) {
 
@@ -84,10 +120,22 @@ fn raycast(rayvec: vec2<f32>) -> RaycastData {
 		}
 
 		let i = u32(tile_coord.y * i32(tilemap.size.x) + tile_coord.x);
-		let texid = tilemap.tile_texids[i];
-		if texid != -1 { // If the tile is solid
+		let tile = tilemap.tiles[i];
+		let texid = tile.texids[0];
+		if texid != -1 && is_tile_object(tile) { // Door or push wall which is partially covering the tile.
+			let entry_distance = select(side_dist.y - delta_dist.y, side_dist.x - delta_dist.x, side == 0);
+			let result = raycast_tile_object(rayvec, tile, tile_coord, entry_distance, min(side_dist.x, side_dist.y));
+			if result.texid != -1 {
+				return result;
+			}
+		}
+		else if texid != -1 { // If the tile is solid
 			var result: RaycastData;
-			result.texid = texid;
+			result.texid = face_texid(tile, side, rayvec);
+			result.side = u32(side);
+			if tile.kind == TILE_GATE {
+				result.lock = u32(tile.offset) + 1u;
+			}
 			switch side {
 				case 0: { // x axis
 					result.distance = side_dist.x - delta_dist.x;
@@ -118,7 +166,95 @@ fn raycast(rayvec: vec2<f32>) -> RaycastData {
 		}
 	} // Loop end means the raycasting is failure.
 
-	return RaycastData(0.0, 1.0, -1, 0.0); // Return default.
+	return RaycastData(0.0, 1.0, -1, 0.0, 0u, 0u); // Return default.
+}
+
+// Texture of the face which the ray hits on the side.
+fn face_texid(tile: Tile, side: i32, rayvec: vec2<f32>) -> i32 {
+	if side == 0 {
+		return select(tile.texids[2], tile.texids[3], rayvec.x > 0.0);
+	}
+	return select(tile.texids[1], tile.texids[0], rayvec.y > 0.0);
+}
+
+fn is_tile_object(tile: Tile) -> bool {
+	return tile.kind != TILE_WALL && tile.kind != TILE_GATE;
+}
+
+fn raycast_tile_object(rayvec: vec2<f32>, tile: Tile, tile_coord: vec2<i32>, entry_distance: f32, exit_distance: f32) -> RaycastData {
+	if tile.kind == TILE_PUSHWALL_X || tile.kind == TILE_PUSHWALL_Y {
+		return raycast_pushwall(rayvec, tile, tile_coord, exit_distance);
+	}
+	return raycast_door(rayvec, tile, tile_coord, entry_distance, exit_distance);
+}
+
+// Hit test the moving push wall block. The block is offset from the tile along its moving axis.
+fn raycast_pushwall(rayvec: vec2<f32>, tile: Tile, tile_coord: vec2<i32>, exit_distance: f32) -> RaycastData {
+	let miss = RaycastData(0.0, 1.0, -1, 0.0, 0u, 0u);
+
+	let along_x = tile.kind == TILE_PUSHWALL_X;
+	let box_min = vec2<f32>(tile_coord) + select(vec2<f32>(0.0, tile.offset), vec2<f32>(tile.offset, 0.0), along_x);
+	let box_max = box_min + vec2<f32>(1.0, 1.0);
+
+	// Slab method
+	let t1 = (box_min - camera.tilepos) / rayvec;
+	let t2 = (box_max - camera.tilepos) / rayvec;
+	let t_near = min(t1, t2);
+	let t_far = max(t1, t2);
+	let distance = max(t_near.x, t_near.y);
+	if distance > min(t_far.x, t_far.y) || distance < 0.0 || distance > exit_distance {
+		return miss;
+	}
+
+	let point_of_collision = camera.tilepos + rayvec * distance;
+	var result: RaycastData;
+	result.texid = tile.texids[0];
+	result.distance = distance;
+	if t_near.x > t_near.y { // x axis
+		let frc = point_of_collision.y - box_min.y;
+		result.u_offset = select(1.0 - frc, frc, rayvec.x > 0.0);
+		result.side = 0u;
+	} else { // y axis
+		let frc = point_of_collision.x - box_min.x;
+		result.u_offset = select(1.0 - frc, frc, rayvec.y < 0.0);
+		result.side = 1u;
+	}
+	result.depth = (result.distance - camera.near) / (camera.far - camera.near);
+	return result;
+}
+
+// Hit test the door slab placed at the middle of the tile.
+// entry_distance, exit_distance: distances where the ray enters and leaves the tile. Same as raycast_door() in raycast.rs.
+fn raycast_door(rayvec: vec2<f32>, tile: Tile, tile_coord: vec2<i32>, entry_distance: f32, exit_distance: f32) -> RaycastData {
+	let miss = RaycastData(0.0, 1.0, -1, 0.0, 0u, 0u);
+
+	// axis: the axis which the slab plane is perpendicular to.
+	let vertical = tile.kind == TILE_DOOR_VERTICAL;
+	let mid = select(f32(tile_coord.y), f32(tile_coord.x), vertical) + 0.5;
+	let ray_axis = select(rayvec.y, rayvec.x, vertical);
+	let pos_axis = select(camera.tilepos.y, camera.tilepos.x, vertical);
+	if ray_axis == 0.0 {
+		return miss;
+	}
+
+	let distance = (mid - pos_axis) / ray_axis;
+	if distance < entry_distance || distance > exit_distance {
+		return miss;
+	}
+
+	let point_of_collision = camera.tilepos + rayvec * distance;
+	let frc = fract(select(point_of_collision.x, point_of_collision.y, vertical));
+	if frc < tile.offset { // Opened part
+		return miss;
+	}
+
+	var result: RaycastData;
+	result.texid = tile.texids[0];
+	result.distance = distance;
+	result.u_offset = frc - tile.offset;
+	result.side = select(1u, 0u, vertical);
+	result.depth = (result.distance - camera.near) / (camera.far - camera.near);
+	return result;
 }
 
 // Helper function for readability.
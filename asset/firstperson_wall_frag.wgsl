@@ -7,9 +7,13 @@ struct RaycastData {
 	distance: f32,
 	depth: f32,
 	texid: i32,
-	u_offset: f32
+	u_offset: f32,
+	lock: u32, // 0: not locked, key id + 1
+	side: u32 // 0: face perpendicular to x axis, 1: y axis
 };
 
+const X_SIDE_SHADE: f32 = 0.7;
+
 struct RaycastDataArray {
 	raycount: u32,
 	data: array<RaycastData>
@@ -59,6 +63,17 @@ fn main(@builtin(position) pos: vec4<f32>) -> FragmentOutput {
 		discard;
 	}
 
+	// Like Wolf3D, faces perpendicular to x axis are darker.
+	if raycast_data_array.data[index].side == 0u {
+		color = vec4<f32>(color.rgb * X_SIDE_SHADE, color.a);
+	}
+
+	// Locked gate is tinted with the key color and barred.
+	let lock = raycast_data_array.data[index].lock;
+	if lock != 0u {
+		color = vec4<f32>(locked_color(color.rgb, lock - 1u, uv), color.a);
+	}
+
 	var out: FragmentOutput;
 	out.color = color;
 	out.depth = raycast_data_array.data[index].depth;
@@ -66,3 +81,18 @@ fn main(@builtin(position) pos: vec4<f32>) -> FragmentOutput {
 	return out;
 }
 
+
+// NOTE: Same as locked_color in minimap_wall.wgsl. Keep them in sync.
+fn locked_color(color: vec3<f32>, key: u32, uv: vec2<f32>) -> vec3<f32> {
+	var key_colors = array<vec3<f32>, 4>(
+		vec3<f32>(1.0, 0.8, 0.2), // gold
+		vec3<f32>(0.75, 0.8, 0.9), // silver
+		vec3<f32>(0.9, 0.3, 0.2),
+		vec3<f32>(0.3, 0.5, 1.0)
+	);
+	let tinted = mix(color, key_colors[key % 4u], 0.5);
+	if fract(uv.x * 5.0) < 0.2 {
+		return tinted * 0.4;
+	}
+	return tinted;
+}
@@ -0,0 +1,32 @@
+@group(0) @binding(0)
+var<uniform> view_proj: mat4x4<f32>;
+
+struct VertexInput {
+	@location(0) position: vec3<f32>,
+	@location(1) color: vec3<f32>,
+	@location(2) uv: vec2<f32>
+}
+
+struct InstanceInput {
+	@location(3) rect: vec4<f32> // xy: offset, zw: size
+}
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>
+}
+
+@vertex
+fn vs_main(in_vert: VertexInput, in_inst: InstanceInput) -> VertexOutput {
+	var out: VertexOutput;
+	var pos = in_vert.position.xy * in_inst.rect.zw + in_inst.rect.xy;
+	out.clip_position = view_proj * vec4<f32>(pos, in_vert.position.z, 1.0);
+	return out;
+}
+
+@group(0) @binding(1)
+var<uniform> color: vec4<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	return color;
+}
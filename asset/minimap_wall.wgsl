@@ -12,14 +12,16 @@ struct VertexInput {
 
 struct InstanceInput {
 	@location(3) pos_offset: vec2<u32>,
-	@location(4) texid: u32
+	@location(4) texid: u32,
+	@location(5) lock: u32 // 0: not locked, key id + 1
 }
 
 struct VertexOutput {
 	@builtin(position) clip_position: vec4<f32>,
 	@location(0) color: vec3<f32>,
 	@location(1) uv: vec2<f32>,
-	@location(2) layer: u32
+	@location(2) layer: u32,
+	@location(3) @interpolate(flat) lock: u32
 }
 
 @vertex
@@ -27,12 +29,17 @@ fn vs_main(
 	in_vert: VertexInput, in_inst: InstanceInput
 ) -> VertexOutput {
 	var out: VertexOutput;
+	if in_inst.texid == 0xffffffffu { // Not a wall. Degenerate triangles are not drawn.
+		out.clip_position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+		return out;
+	}
 	var offset = vec2<f32>(grid_size * vec2<f32>(in_inst.pos_offset));
 	var scaled_vertex = in_vert.position.xy * grid_size;
 	out.clip_position = view_proj * vec4<f32>(scaled_vertex.xy + offset, in_vert.position.z, 1.0);
 	out.color = in_vert.color;
 	out.uv = in_vert.uv;
 	out.layer = in_inst.texid;
+	out.lock = in_inst.lock;
 	return out;
 }
 
@@ -45,5 +52,25 @@ var texture_sampler: sampler;
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 	var tex_color = textureSample(texture_array, texture_sampler, in.uv, in.layer);
-	return vec4<f32>(tex_color.rgb * in.color, tex_color.a);
+	var color = tex_color.rgb * in.color;
+	if in.lock != 0u { // Locked gate
+		color = locked_color(color, in.lock - 1u, in.uv);
+	}
+	return vec4<f32>(color, tex_color.a);
+}
+
+// Tinted with the key color and barred.
+// NOTE: Same as locked_color in firstperson_wall_frag.wgsl. Keep them in sync.
+fn locked_color(color: vec3<f32>, key: u32, uv: vec2<f32>) -> vec3<f32> {
+	var key_colors = array<vec3<f32>, 4>(
+		vec3<f32>(1.0, 0.8, 0.2), // gold
+		vec3<f32>(0.75, 0.8, 0.9), // silver
+		vec3<f32>(0.9, 0.3, 0.2),
+		vec3<f32>(0.3, 0.5, 1.0)
+	);
+	let tinted = mix(color, key_colors[key % 4u], 0.5);
+	if fract(uv.x * 5.0) < 0.2 {
+		return tinted * 0.4;
+	}
+	return tinted;
 }
\ No newline at end of file
@@ -7,28 +7,33 @@ struct Rect {
 	left: u32, right: u32, top: u32, bottom: u32
 }
 
+struct Layer {
+	index: u32
+}
+
 @group(0) @binding(0) var<uniform> surface: SurfaceInfo;
 @group(0) @binding(1) var<uniform> rect: Rect;
+@group(0) @binding(2) var<uniform> layer: Layer;
 
-@group(1) @binding(0) var tex: texture_2d<f32>;
+@group(1) @binding(0) var tex: texture_2d_array<f32>;
 @group(1) @binding(1) var smp: sampler;
 
 struct VertexOutput {
 	@builtin(position) clip_position: vec4<f32>,
-	@location(0) uv: vec2<f32>,
-	@location(1) depth: f32
+	@location(0) uv: vec2<f32>
 }
 
 @vertex
 fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOutput {
-	let half_surface = SurfaceInfo(
-		surface.width / 2u, surface.height / 2u
+	let half_surface = vec2<f32>(
+		f32(surface.width) * 0.5, f32(surface.height) * 0.5
 	);
+	// NOTE: The rect can be out of the surface(e.g. lowered weapon), so convert to f32 before subtraction.
 	let rect_norm = vec4<f32>(
-		f32(rect.left) / f32(half_surface.width) - 1.0,
-		f32(rect.right) / f32(half_surface.width) - 1.0,
-		-f32(rect.top) / f32(half_surface.height) + 1.0,
-		-f32(rect.bottom) / f32(half_surface.height) + 1.0,
+		f32(rect.left) / half_surface.x - 1.0,
+		f32(rect.right) / half_surface.x - 1.0,
+		-f32(rect.top) / half_surface.y + 1.0,
+		-f32(rect.bottom) / half_surface.y + 1.0,
 	);
 	
 	var pos: vec2<f32>;
@@ -62,21 +67,16 @@ fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOutput {
 	}
 
 	var out: VertexOutput;
-	out.clip_position = vec4<f32>(pos, 1.0, 1.0);
+	out.clip_position = vec4<f32>(pos, 0.0, 1.0);
 	out.uv = uv;
-	out.depth = 0.0; //TODO: Accept depth(distance).
 	return out;
 }
 
-struct FragmentOutput {
-	@location(0) color: vec4<f32>,
-	@builtin(frag_depth) depth: f32
-}
-
 @fragment
-fn fs_main(in: VertexOutput) -> FragmentOutput {
-	var out: FragmentOutput;
-	out.color = textureSample(tex, smp, in.uv);
-	out.depth = in.depth;
-	return out;
-}
\ No newline at end of file
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	let color = textureSample(tex, smp, in.uv, layer.index);
+	if color.a < 0.5 {
+		discard;
+	}
+	return color;
+}
@@ -50,13 +50,15 @@ pub enum AssetServerError {
 
 impl AssetServer {
 	pub fn create_test_asset_server(device: &wgpu::Device, queue: &wgpu::Queue) -> AssetServer {
-		const SHADER_SOURCES: [(&'static str, &'static str); 7] = [
+		const SHADER_SOURCES: [(&'static str, &'static str); 9] = [
 			("fillscreen", "asset/fillscreen.wgsl"),
 			("firstperson_wall_compute", "asset/firstperson_wall_compute.wgsl"),
 			("firstperson_wall_frag", "asset/firstperson_wall_frag.wgsl"),
 			("firstperson_floorceil", "asset/firstperson_floorceil.wgsl"),
+			("firstperson_sprite", "asset/firstperson_sprite.wgsl"),
 			("minimap_actor", "asset/minimap_actor.wgsl"),
 			("minimap_wall", "asset/minimap_wall.wgsl"),
+			("minimap_rect", "asset/minimap_rect.wgsl"),
 			("texture_blit", "asset/texture_screen_blit.wgsl")
 		];
 		const IMAGES: [(&'static str, &'static str); 2] = [
@@ -1,4 +1,8 @@
 
 fn main() -> Result<(), winit::error::EventLoopError> {
-	rulf_3d::Rulf3D::testrun()
-}
\ No newline at end of file
+	// dev [demo file to play]
+	match std::env::args().nth(1) {
+		Some(demo) => rulf_3d::Rulf3D::playdemo(demo),
+		None => rulf_3d::Rulf3D::testrun()
+	}
+}
@@ -0,0 +1,31 @@
+// Run the game logic without a window or GPU.
+// headless [demo file]: Play the demo, or run the test map for 10 seconds without input.
+
+use rulf_3d::game::{Demo, GameWorld, MapData, PlayerInput};
+
+fn main() {
+	let game_world = match std::env::args().nth(1) {
+		Some(path) => match Demo::load(path) {
+			Ok(demo) => demo.play(),
+			Err(e) => {
+				eprintln!("{}", e);
+				std::process::exit(1);
+			}
+		},
+		None => {
+			let map = MapData::load("asset/test.map").unwrap_or_else(|e| {
+				eprintln!("{}", e);
+				MapData::test_map()
+			});
+			let mut game_world = GameWorld::from_map(map);
+			game_world.step_ticks(&PlayerInput::default(), 1.0 / 60.0, 600);
+			game_world
+		}
+	};
+
+	let stats = game_world.get_player_stats();
+	println!("player: {:?}", game_world.get_player().transform);
+	println!("health: {}, armor: {}, lives: {}, score: {}", game_world.get_player_health(), stats.armor, stats.lives, stats.score);
+	let (found, total) = game_world.get_secret_count();
+	println!("secrets: {}/{}", found, total);
+}
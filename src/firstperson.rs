@@ -1,6 +1,7 @@
 use std::f32::consts::PI;
+use std::collections::BTreeMap;
 use crate::{
-	game::{GameWorld, TileType}, 
+	game::{GameWorld, TileMap, TileType, TileDirty, TileRect, TextureAnimations, DoorOrientation}, 
 	webgpu::{WebGPU, WebGPUDevice, WebGPUSurface},
 	asset::AssetServer
 };
@@ -25,7 +26,63 @@ struct RaycastData {
 	distance: f32,
 	depth: f32,
 	texid: u32,
-	u_offset: f32
+	u_offset: f32,
+	lock: u32, // 0: not locked, key id + 1
+	side: u32 // 0: face perpendicular to x axis, 1: y axis
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct WallTile {
+	texids: [i32; 4], // north(-y), south, east(+x), west faces. -1: not solid
+	kind: u32,
+	offset: f32 // door: open amount, push wall: block offset from the tile along moving axis, gate: key id
+}
+
+impl WallTile {
+	const WALL: u32 = 0;
+	const DOOR_VERTICAL: u32 = 1;
+	const DOOR_HORIZONTAL: u32 = 2;
+	const PUSHWALL_X: u32 = 3; // block moving along x axis
+	const PUSHWALL_Y: u32 = 4;
+	const GATE: u32 = 5;
+}
+
+// Floor and ceiling texture ids of the tile. -1: not empty
+fn empty_tile(tilemap: &TileMap, coord: glam::UVec2) -> glam::IVec2 {
+	match tilemap.get_floor_ceil(coord) {
+		Some((t1, t2)) => glam::ivec2(t1 as i32, t2 as i32),
+		None => glam::ivec2(-1, -1)
+	}
+}
+
+fn wall_tile(tilemap: &TileMap, coord: glam::UVec2) -> WallTile {
+	// Moving push walls cover the empty tiles.
+	for pushwall in &tilemap.pushwalls {
+		let (first, second) = pushwall.covering_tiles();
+		if first == coord || second == Some(coord) {
+			let position = pushwall.position();
+			let (kind, offset) = match pushwall.direction.x != 0 {
+				true => (WallTile::PUSHWALL_X, position.x - coord.x as f32),
+				false => (WallTile::PUSHWALL_Y, position.y - coord.y as f32)
+			};
+			return WallTile { texids: [pushwall.texid as i32; 4], kind, offset };
+		}
+	}
+	match tilemap.get_data()[(coord.y * tilemap.width + coord.x) as usize] {
+		TileType::Empty(_, _) => WallTile { texids: [-1; 4], kind: WallTile::WALL, offset: 0.0 },
+		TileType::Wall(faces) => WallTile { texids: faces.to_array().map(|id| id as i32), kind: WallTile::WALL, offset: 0.0 },
+		TileType::PushWall(id) => WallTile { texids: [id as i32; 4], kind: WallTile::WALL, offset: 0.0 },
+		TileType::Gate(id, key) => WallTile { texids: [id as i32; 4], kind: WallTile::GATE, offset: key as f32 },
+		TileType::Door(orientation, id) => WallTile {
+			texids: [id as i32; 4],
+			kind: match orientation {
+				DoorOrientation::Vertical => WallTile::DOOR_VERTICAL,
+				DoorOrientation::Horizontal => WallTile::DOOR_HORIZONTAL
+			},
+			offset: tilemap.doors.get(&coord.to_array()).map_or(0.0, |door| door.open_amount)
+		}
+	}
 }
 
 #[repr(C)]
@@ -58,6 +115,29 @@ struct WallCameraInfo {
 	far: f32
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteInstance {
+	position: glam::Vec2, // tile space
+	texid: u32,
+	_padding: u32
+}
+
+impl SpriteInstance {
+	const INST_ATTR: [wgpu::VertexAttribute; 2] = [
+		wgpu::VertexAttribute {
+			format: wgpu::VertexFormat::Float32x2,
+			offset: 0,
+			shader_location: 0
+		},
+		wgpu::VertexAttribute {
+			format: wgpu::VertexFormat::Uint32,
+			offset: std::mem::size_of::<glam::Vec2>() as u64,
+			shader_location: 1
+		}
+	];
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Rect {
@@ -68,12 +148,17 @@ pub struct Renderer {
 	pub fov: f32,
 	floorceil_data: floorceil::Data,
 	wall_data: wall::Data,
-	test_blit_data: sprite::BlitData,
-	depth_texture: wgpu::Texture
+	sprite_data: sprite::Data,
+	view_model_data: sprite::OverlayData,
+	depth_texture: wgpu::Texture,
+	tilemap_revision: u64, // of the uploaded tiles. 0: nothing uploaded yet
+	texture_frames: BTreeMap<u32, u32> // uploaded frames of the animated textures
 }
 
 impl Renderer {
-	pub fn render(&mut self, webgpu: &WebGPU, game_world: &GameWorld, clear_color: &wgpu::Color) {
+	// alpha: interpolation between the last two ticks.
+	pub fn render(&mut self, webgpu: &WebGPU, game_world: &GameWorld, alpha: f32, clear_color: &wgpu::Color) {
+		let player = game_world.get_interpolated_transform(game_world.get_player_id(), alpha).unwrap_or(game_world.get_player().transform);
 		let output = webgpu.get_surface().get_current_texture().unwrap();
 		
 		let surface_info = SurfaceInfo {
@@ -82,11 +167,11 @@ impl Renderer {
 		};
 
 		let tan_half_fov = (self.fov/2.0).min(89.0f32.to_radians()).tan();
-		let cam_dir = game_world.get_player_forward_vector();
+		let cam_dir = player.forward();
 		let cam_plane = cam_dir.perp() * 0.5;
 		let cam_len = cam_plane.length() * 2.0 / tan_half_fov; // BUG: Coincidence Problem. Fixed with magic number 2.0, still not solved completely.
 		let cam_vec = cam_dir * cam_len;
-		let cam_pos = game_world.get_player_position() / game_world.get_grid_size();
+		let cam_pos = player.position / game_world.get_grid_size();
 
 		let floorceil_camera_info = FloorCeilCameraInfo {
 			pos: cam_pos,
@@ -106,34 +191,47 @@ impl Renderer {
 			far: CAMERA_FAR
 		};
 
-		let tilemap = game_world.get_tilemap();
-		let tilemap_empty_data: Vec<_> = tilemap.data.clone().into_iter().map(|ty| match ty {
-			TileType::Empty(t1, t2) => glam::ivec2(t1 as i32, t2 as i32),
-			TileType::Wall(_) => glam::ivec2(-1, -1)
-		}).collect();
-		let tilemap_wall_data: Vec<_> = tilemap.data.clone().into_iter().map(|ty| match ty {
-			TileType::Empty(_, _) => -1,
-			TileType::Wall(id) => id as i32
+
+		// Sort sprites far to near for alpha blending.
+		let grid_size = game_world.get_grid_size();
+		let mut sprites: Vec<_> = game_world.get_entities().iter().filter_map(|(id, entity)| {
+			let transform = game_world.get_interpolated_transform(id, alpha)?;
+			entity.sprite.map(|sprite| SpriteInstance {
+				position: transform.position / grid_size, texid: sprite.layer(&transform, player.position), _padding: 0
+			})
 		}).collect();
-		let tilemap_size = glam::uvec2(tilemap.width, tilemap.height);
+		sprites.sort_by(|a, b| (b.position - cam_pos).dot(cam_dir).total_cmp(&(a.position - cam_pos).dot(cam_dir)));
+		sprites.truncate(sprite::Data::MAX_SPRITE_INSTANCE as usize);
 
 		let (device, queue) = webgpu.get_device();
 
 		queue.write_buffer(&self.floorceil_data.surface_info, 0, bytemuck::cast_slice(&[surface_info]));
 		queue.write_buffer(&self.floorceil_data.camera_info, 0, bytemuck::cast_slice(&[floorceil_camera_info]));
-		queue.write_buffer(&self.floorceil_data.tilemap_info, 0, bytemuck::cast_slice(&[tilemap_size]));
-		queue.write_buffer(&self.floorceil_data.tilemap_info, std::mem::size_of_val(&tilemap_size) as u64, bytemuck::cast_slice(&tilemap_empty_data));
 
 		queue.write_buffer(&self.wall_data.surface_info_buffer, 0, bytemuck::cast_slice(&[surface_info]));
 		queue.write_buffer(&self.wall_data.camera_info, 0, bytemuck::cast_slice(&[wall_camera_info]));
-		queue.write_buffer(&self.wall_data.tilemap_data, 0, bytemuck::cast_slice(&[tilemap_size]));
-		queue.write_buffer(&self.wall_data.tilemap_data, std::mem::size_of_val(&tilemap_size) as u64, bytemuck::cast_slice(&tilemap_wall_data));
 		queue.write_buffer(&self.wall_data.raycast_data_array_buffer, 0, bytemuck::cast_slice(&[surface_info.width]));
-
-
-		queue.write_buffer(&self.test_blit_data.surface_info, 0, bytemuck::bytes_of(&surface_info));
-		let rect = Rect {left:0, right: 600, top: 0, bottom: 600};
-		queue.write_buffer(&self.test_blit_data.rect, 0, bytemuck::bytes_of(&rect));
+		self.upload_tilemap(queue, game_world.get_tilemap());
+		self.upload_texture_frames(queue, &game_world.get_tilemap().animations, game_world.get_time());
+
+
+		queue.write_buffer(&self.sprite_data.instances, 0, bytemuck::cast_slice(&sprites));
+		self.sprite_data.instances_len = sprites.len() as u32;
+
+		// Weapon view model: a square of the half surface height at the bottom center.
+		let view_frame = game_world.get_player_weapons().get_view_frame();
+		let view_size = surface_info.height / 2;
+		let view_lowered = (view_frame.lowered * view_size as f32) as u32;
+		let view_rect = Rect {
+			left: surface_info.width.saturating_sub(view_size) / 2,
+			right: (surface_info.width + view_size) / 2,
+			top: surface_info.height - view_size + view_lowered,
+			bottom: surface_info.height + view_lowered
+		};
+		queue.write_buffer(&self.view_model_data.surface_info, 0, bytemuck::cast_slice(&[surface_info]));
+		queue.write_buffer(&self.view_model_data.rect, 0, bytemuck::cast_slice(&[view_rect]));
+		queue.write_buffer(&self.view_model_data.layer, 0, bytemuck::cast_slice(&[view_frame.layer]));
+		self.view_model_data.visible = game_world.get_player_stats().is_alive();
 
 		let size = output.texture.size();
 		let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -210,16 +308,70 @@ impl Renderer {
 		render_pass.set_bind_group(1, &self.wall_data.render_bind_groups[1], &[]);
 		render_pass.draw(0..4, 0..1);
 
-		render_pass.set_pipeline(&self.test_blit_data.pipeline);
-		render_pass.set_bind_group(0, &self.test_blit_data.bind_groups[0], &[]);
-		render_pass.set_bind_group(1, &self.test_blit_data.bind_groups[1], &[]);
-		render_pass.draw(0..4, 0..1);
+		render_pass.set_pipeline(&self.sprite_data.pipeline);
+		render_pass.set_bind_group(0, &self.sprite_data.bind_groups[0], &[]);
+		render_pass.set_bind_group(1, &self.sprite_data.bind_groups[1], &[]);
+		render_pass.set_vertex_buffer(0, self.sprite_data.instances.slice(..));
+		render_pass.draw(0..4, 0..self.sprite_data.instances_len);
+
+		if self.view_model_data.visible {
+			render_pass.set_pipeline(&self.view_model_data.pipeline);
+			render_pass.set_bind_group(0, &self.view_model_data.bind_groups[0], &[]);
+			render_pass.set_bind_group(1, &self.view_model_data.bind_groups[1], &[]);
+			render_pass.draw(0..4, 0..1);
+		}
 
 		drop(render_pass);
 
 		queue.submit(Some(encoder.finish()));
 		output.present();
 	}
+
+	// Upload only the tiles changed after the last upload.
+	fn upload_tilemap(&mut self, queue: &wgpu::Queue, tilemap: &TileMap) {
+		let tilemap_size = glam::uvec2(tilemap.width, tilemap.height);
+		let header_size = std::mem::size_of_val(&tilemap_size) as u64;
+		let rect = match tilemap.get_dirty_since(self.tilemap_revision) {
+			TileDirty::Clean => return,
+			TileDirty::Tiles(rect) => rect,
+			TileDirty::All => {
+				queue.write_buffer(&self.floorceil_data.tilemap_info, 0, bytemuck::cast_slice(&[tilemap_size]));
+				queue.write_buffer(&self.wall_data.tilemap_data, 0, bytemuck::cast_slice(&[tilemap_size]));
+				TileRect { min: glam::UVec2::ZERO, max: tilemap_size.saturating_sub(glam::UVec2::ONE) }
+			}
+		};
+		self.tilemap_revision = tilemap.get_revision();
+		if tilemap.get_data().is_empty() {
+			return;
+		}
+
+		// Ranges of tile indices. Rows spanning the whole width are contiguous.
+		let width = tilemap.width;
+		let ranges: Vec<(u32, u32)> = match rect.min.x == 0 && rect.max.x + 1 == width {
+			true => vec![(rect.min.y * width, (rect.max.y + 1) * width)],
+			false => (rect.min.y..=rect.max.y).map(|y| (y * width + rect.min.x, y * width + rect.max.x + 1)).collect()
+		};
+		for (start, end) in ranges {
+			let coords = (start..end).map(|i| glam::uvec2(i % width, i / width));
+			let empty_data: Vec<_> = coords.clone().map(|coord| empty_tile(tilemap, coord)).collect();
+			let wall_data: Vec<_> = coords.map(|coord| wall_tile(tilemap, coord)).collect();
+			let offset = start as u64;
+			queue.write_buffer(&self.floorceil_data.tilemap_info, header_size + offset * std::mem::size_of::<glam::IVec2>() as u64, bytemuck::cast_slice(&empty_data));
+			queue.write_buffer(&self.wall_data.tilemap_data, header_size + offset * std::mem::size_of::<WallTile>() as u64, bytemuck::cast_slice(&wall_data));
+		}
+	}
+
+	// Upload only the frames changed since the last upload. The tiles keep the base texture ids.
+	fn upload_texture_frames(&mut self, queue: &wgpu::Queue, animations: &TextureAnimations, time: f32) {
+		let frames = animations.frames_at(time);
+		let changed = frames.iter().filter(|(texid, frame)| self.texture_frames.get(texid) != Some(frame)).map(|(texid, frame)| (*texid, *frame));
+		let removed = self.texture_frames.keys().filter(|texid| !frames.contains_key(texid)).map(|texid| (*texid, *texid));
+		for (texid, frame) in changed.chain(removed).filter(|(texid, _)| *texid < wall::Data::MAX_TEXTURES) {
+			queue.write_buffer(&self.wall_data.texture_frames, texid as u64 * std::mem::size_of::<i32>() as u64, bytemuck::cast_slice(&[frame as i32]));
+		}
+		self.texture_frames = frames;
+	}
+
 	pub fn new(webgpu: &WebGPU, asset_server: &AssetServer) -> Self {
 		let (device, _) = webgpu.get_device();
 		let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -234,14 +386,20 @@ impl Renderer {
 			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
 			view_formats: &[]
 		});
+		let wall_data = wall::Data::new(webgpu, asset_server);
+		let sprite_data = sprite::Data::new(webgpu, asset_server, &wall_data);
+		let floorceil_data = floorceil::Data::new(webgpu, asset_server, &wall_data);
 		Self {
 			// BUG: Gap Problem. There's a gap between floorceils and walls. Both leftside and rightside has gaps but the rightside seems bigger.
 			// Fixing by magic number. Why does fov value influence floorceil's height?
 			fov: PI / 2.3,
-			wall_data: wall::Data::new(webgpu, asset_server), 
-			floorceil_data: floorceil::Data::new(webgpu, asset_server), 
-			test_blit_data: sprite::BlitData::test_blit(webgpu, asset_server),
-			depth_texture
+			wall_data,
+			floorceil_data, 
+			sprite_data,
+			view_model_data: sprite::OverlayData::new(webgpu, asset_server, "buddha16_5x2"),
+			depth_texture,
+			tilemap_revision: 0,
+			texture_frames: BTreeMap::new()
 		}
 	}
 }
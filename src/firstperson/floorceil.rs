@@ -2,7 +2,7 @@ use crate::{
 	webgpu::{WebGPU, WebGPUDevice, WebGPUConfig},
 	asset::AssetServer
 };
-use super::{SurfaceInfo, FloorCeilCameraInfo, ScanlineData};
+use super::{SurfaceInfo, FloorCeilCameraInfo, ScanlineData, wall};
 
 
 
@@ -32,7 +32,7 @@ impl Data {
 }
 
 impl Data {
-	pub fn new(webgpu: &WebGPU, asset_server: &AssetServer) -> Self {
+	pub fn new(webgpu: &WebGPU, asset_server: &AssetServer, wall_data: &wall::Data) -> Self {
 		let (device, _) = webgpu.get_device();
 		let surface_info = device.create_buffer(&wgpu::BufferDescriptor {
 			label: Some("floorceil::Data.surface_info"),
@@ -115,6 +115,16 @@ impl Data {
 						},
 						count: None
 					},
+					wgpu::BindGroupLayoutEntry {
+						binding: 3,
+						visibility: wgpu::ShaderStages::COMPUTE | wgpu::ShaderStages::FRAGMENT,
+						ty: wgpu::BindingType::Buffer { 
+							ty: wgpu::BufferBindingType::Storage { read_only: true },
+							has_dynamic_offset: false,
+							min_binding_size: None
+						},
+						count: None
+					},
 				]
 			}),
 			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -191,6 +201,10 @@ impl Data {
 					wgpu::BindGroupEntry {
 						binding: 2,
 						resource: tilemap_info.as_entire_binding()
+					},
+					wgpu::BindGroupEntry {
+						binding: 3,
+						resource: wall_data.texture_frames.as_entire_binding()
 					}
 				]
 			}),
@@ -3,11 +3,202 @@ use crate::{
 	webgpu::{WebGPU, WebGPUDevice, WebGPUConfig},
 	asset::AssetServer
 };
-use super::{SurfaceInfo, Rect};
+use super::{SurfaceInfo, Rect, SpriteInstance, wall};
 
-pub struct BlitData {
+// World space billboard sprites drawn after walls and floors.
+pub struct Data {
+	pub instances: wgpu::Buffer,
+	pub instances_len: u32,
+
+	pub bind_groups: [wgpu::BindGroup; 2],
+	pub pipeline: wgpu::RenderPipeline,
+
+	_texview: wgpu::TextureView,
+	_sampler: wgpu::Sampler
+}
+
+impl Data {
+	pub const MAX_SPRITE_INSTANCE: u64 = 4096;
+
+	// Shares surface info, camera info and raycasting result with the wall pass.
+	pub fn new(webgpu: &WebGPU, asset_server: &AssetServer, wall_data: &wall::Data) -> Self {
+		let (device, _) = webgpu.get_device();
+		let instances = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("sprite::Data.instances"),
+			size: std::mem::size_of::<SpriteInstance>() as u64 * Self::MAX_SPRITE_INSTANCE,
+			usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false
+		});
+
+		let texture = asset_server.get_texture("buddha16_5x2").unwrap();
+		let texview = texture.create_view(&wgpu::TextureViewDescriptor {
+			label: Some("sprite::Data._texview"),
+			dimension: Some(wgpu::TextureViewDimension::D2Array),
+			..Default::default()
+		});
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+		let bind_group_layouts = [
+			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+				label: Some("sprite::Data bind group layout 0: surface, camera and raydata"),
+				entries: &[
+					wgpu::BindGroupLayoutEntry {
+						binding: 0,
+						visibility: wgpu::ShaderStages::FRAGMENT,
+						ty: wgpu::BindingType::Buffer {
+							ty: wgpu::BufferBindingType::Uniform,
+							has_dynamic_offset: false,
+							min_binding_size: None
+						},
+						count: None
+					},
+					wgpu::BindGroupLayoutEntry {
+						binding: 1,
+						visibility: wgpu::ShaderStages::VERTEX,
+						ty: wgpu::BindingType::Buffer {
+							ty: wgpu::BufferBindingType::Uniform,
+							has_dynamic_offset: false,
+							min_binding_size: None
+						},
+						count: None
+					},
+					wgpu::BindGroupLayoutEntry {
+						binding: 2,
+						visibility: wgpu::ShaderStages::FRAGMENT,
+						ty: wgpu::BindingType::Buffer {
+							ty: wgpu::BufferBindingType::Storage { read_only: true },
+							has_dynamic_offset: false,
+							min_binding_size: None
+						},
+						count: None
+					}
+				]
+			}),
+			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+				label: Some("sprite::Data bind group layout 1: texture array and sampler"),
+				entries: &[
+					wgpu::BindGroupLayoutEntry {
+						binding: 0,
+						visibility: wgpu::ShaderStages::FRAGMENT,
+						ty: wgpu::BindingType::Texture {
+							sample_type: wgpu::TextureSampleType::Float { filterable: true },
+							view_dimension: wgpu::TextureViewDimension::D2Array,
+							multisampled: false
+						},
+						count: None
+					},
+					wgpu::BindGroupLayoutEntry {
+						binding: 1,
+						visibility: wgpu::ShaderStages::FRAGMENT,
+						ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+						count: None
+					}
+				]
+			})
+		];
+
+		let bind_groups = [
+			device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: Some("sprite::Data.bind_groups[0]"),
+				layout: &bind_group_layouts[0],
+				entries: &[
+					wgpu::BindGroupEntry {
+						binding: 0,
+						resource: wall_data.surface_info_buffer.as_entire_binding()
+					},
+					wgpu::BindGroupEntry {
+						binding: 1,
+						resource: wall_data.camera_info.as_entire_binding()
+					},
+					wgpu::BindGroupEntry {
+						binding: 2,
+						resource: wall_data.raycast_data_array_buffer.as_entire_binding()
+					}
+				]
+			}),
+			device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: Some("sprite::Data.bind_groups[1]"),
+				layout: &bind_group_layouts[1],
+				entries: &[
+					wgpu::BindGroupEntry {
+						binding: 0,
+						resource: wgpu::BindingResource::TextureView(&texview)
+					},
+					wgpu::BindGroupEntry {
+						binding: 1,
+						resource: wgpu::BindingResource::Sampler(&sampler)
+					}
+				]
+			})
+		];
+
+		let shader = asset_server.get_shader("firstperson_sprite").unwrap();
+
+		let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("sprite::Data pipeline layout"),
+			bind_group_layouts: &[&bind_group_layouts[0], &bind_group_layouts[1]],
+			push_constant_ranges: &[]
+		});
+
+		let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("sprite::Data.pipeline"),
+			layout: Some(&pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: shader,
+				entry_point: "vs_main",
+				buffers: &[
+					wgpu::VertexBufferLayout {
+						array_stride: std::mem::size_of::<SpriteInstance>() as u64,
+						step_mode: wgpu::VertexStepMode::Instance,
+						attributes: &SpriteInstance::INST_ATTR
+					}
+				],
+				compilation_options: wgpu::PipelineCompilationOptions::default()
+			},
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleStrip,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: None,
+				unclipped_depth: false,
+				polygon_mode: wgpu::PolygonMode::Fill,
+				conservative: false
+			},
+			depth_stencil: Some(wgpu::DepthStencilState {
+				format: wgpu::TextureFormat::Depth32Float,
+				depth_write_enabled: true,
+				depth_compare: wgpu::CompareFunction::LessEqual,
+				stencil: wgpu::StencilState::default(),
+				bias: wgpu::DepthBiasState::default()
+			}),
+			multisample: wgpu::MultisampleState::default(),
+			fragment: Some(wgpu::FragmentState {
+				module: shader,
+				entry_point: "fs_main",
+				targets: &[Some(wgpu::ColorTargetState {
+					format: webgpu.get_config().format,
+					blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+					write_mask: wgpu::ColorWrites::all()
+				})],
+				compilation_options: wgpu::PipelineCompilationOptions::default()
+			}),
+			multiview: None,
+			cache: None
+		});
+
+		Self {
+			instances, instances_len: 0, bind_groups, pipeline, _texview: texview, _sampler: sampler
+		}
+	}
+}
+
+// Screen space overlay drawn after everything regardless of depth. (e.g. weapon view model)
+// The texture layer and the rect are updated every frame for animation.
+pub struct OverlayData {
 	pub surface_info: wgpu::Buffer,
 	pub rect: wgpu::Buffer,
+	pub layer: wgpu::Buffer,
+	pub visible: bool,
 
 	pub bind_groups: [wgpu::BindGroup; 2],
 	pub pipeline: wgpu::RenderPipeline,
@@ -16,27 +207,34 @@ pub struct BlitData {
 	_sampler: wgpu::Sampler
 }
 
-impl BlitData {
-	pub fn test_blit(webgpu: &WebGPU, asset_server: &AssetServer) -> Self {
+impl OverlayData {
+	// texture_name: grid texture array of the animation frames.
+	pub fn new(webgpu: &WebGPU, asset_server: &AssetServer, texture_name: &str) -> Self {
 		let (device, _) = webgpu.get_device();
 		let surface_info = device.create_buffer(&wgpu::BufferDescriptor {
-			label: Some("sprite::BlitData.surface_info"),
+			label: Some("sprite::OverlayData.surface_info"),
 			size: std::mem::size_of::<SurfaceInfo>() as u64,
 			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false
 		});
 		let rect = device.create_buffer(&wgpu::BufferDescriptor {
-			label: Some("sprite::BlitData.rect"),
+			label: Some("sprite::OverlayData.rect"),
 			size: std::mem::size_of::<Rect>() as u64,
 			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false
 		});
+		let layer = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("sprite::OverlayData.layer"),
+			size: std::mem::size_of::<[u32; 4]>() as u64, // padded to 16 bytes for the uniform
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false
+		});
 
-		let texture = asset_server.get_texture("buddha16_5x2").unwrap();
+		let texture = asset_server.get_texture(texture_name).unwrap();
 
 		let texview = texture.create_view(&wgpu::TextureViewDescriptor {
-			label: Some("sprite::BlitData._texview"),
-			dimension: Some(wgpu::TextureViewDimension::D2),
+			label: Some("sprite::OverlayData._texview"),
+			dimension: Some(wgpu::TextureViewDimension::D2Array),
 			..Default::default()
 		});
 
@@ -44,7 +242,7 @@ impl BlitData {
 
 		let bind_group_layouts = [
 			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-				label: Some("BlitData bind group layout 0: info buffer"),
+				label: Some("OverlayData bind group layout 0: info buffer"),
 				entries: &[
 					wgpu::BindGroupLayoutEntry {
 						binding: 0,
@@ -65,18 +263,28 @@ impl BlitData {
 							min_binding_size: None
 						},
 						count: None
+					},
+					wgpu::BindGroupLayoutEntry {
+						binding: 2,
+						visibility: wgpu::ShaderStages::FRAGMENT,
+						ty: wgpu::BindingType::Buffer {
+							ty: wgpu::BufferBindingType::Uniform,
+							has_dynamic_offset: false,
+							min_binding_size: None
+						},
+						count: None
 					}
 				]
 			}),
 			device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-				label: Some("BlitData bind group layout 1: texture"),
+				label: Some("OverlayData bind group layout 1: texture"),
 				entries: &[
 					wgpu::BindGroupLayoutEntry {
 						binding: 0,
 						visibility: wgpu::ShaderStages::FRAGMENT,
 						ty: wgpu::BindingType::Texture {
 							sample_type: wgpu::TextureSampleType::Float { filterable: true },
-							view_dimension: wgpu::TextureViewDimension::D2,
+							view_dimension: wgpu::TextureViewDimension::D2Array,
 							multisampled: false
 						},
 						count: None
@@ -93,7 +301,7 @@ impl BlitData {
 
 		let bind_groups = [
 			device.create_bind_group(&wgpu::BindGroupDescriptor {
-				label: Some("sprite::BlitData.bind_groups[0]"),
+				label: Some("sprite::OverlayData.bind_groups[0]"),
 				layout: &bind_group_layouts[0],
 				entries: &[
 					wgpu::BindGroupEntry {
@@ -103,11 +311,15 @@ impl BlitData {
 					wgpu::BindGroupEntry {
 						binding: 1,
 						resource: rect.as_entire_binding()
+					},
+					wgpu::BindGroupEntry {
+						binding: 2,
+						resource: layer.as_entire_binding()
 					}
 				]
 			}),
 			device.create_bind_group(&wgpu::BindGroupDescriptor {
-				label: Some("sprite::BlitData.bind_groups[1]"),
+				label: Some("sprite::OverlayData.bind_groups[1]"),
 				layout: &bind_group_layouts[1],
 				entries: &[
 					wgpu::BindGroupEntry {
@@ -125,13 +337,13 @@ impl BlitData {
 		let shader = asset_server.get_shader("texture_blit").unwrap();
 
 		let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-			label: Some("sprite BlitData pipeline layout"),
+			label: Some("sprite OverlayData pipeline layout"),
 			bind_group_layouts: &[&bind_group_layouts[0], &bind_group_layouts[1]],
 			push_constant_ranges: &[]
 		});
 
 		let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-			label: Some("sprite::BlitData.pipeline"),
+			label: Some("sprite::OverlayData.pipeline"),
 			layout: Some(&pipeline_layout),
 			vertex: wgpu::VertexState {
 				module: &shader,
@@ -148,10 +360,11 @@ impl BlitData {
 				polygon_mode: wgpu::PolygonMode::Fill,
 				conservative: false
 			},
+			// Always on top of the scene.
 			depth_stencil: Some(wgpu::DepthStencilState {
 				format: wgpu::TextureFormat::Depth32Float,
-				depth_write_enabled: true,
-				depth_compare: wgpu::CompareFunction::LessEqual,
+				depth_write_enabled: false,
+				depth_compare: wgpu::CompareFunction::Always,
 				stencil: wgpu::StencilState::default(),
 				bias: wgpu::DepthBiasState::default()
 			}),
@@ -171,165 +384,7 @@ impl BlitData {
 		});
 
 		Self {
-			surface_info, rect, bind_groups, pipeline, _texview: texview, _sampler: sampler
+			surface_info, rect, layer, visible: false, bind_groups, pipeline, _texview: texview, _sampler: sampler
 		}
 	}
-
-	// pub fn new(webgpu: &WebGPU, asset_server: &AssetServer) -> Self {
-	// 	let (device, _) = webgpu.get_device();
-	// 	let surface_info = device.create_buffer(&wgpu::BufferDescriptor {
-	// 		label: Some("sprite::BlitData.surface_info"),
-	// 		size: std::mem::size_of::<SurfaceInfo>() as u64,
-	// 		usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-	// 		mapped_at_creation: false
-	// 	});
-	// 	let rect = device.create_buffer(&wgpu::BufferDescriptor {
-	// 		label: Some("sprite::BlitData.rect"),
-	// 		size: std::mem::size_of::<Rect>() as u64,
-	// 		usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-	// 		mapped_at_creation: false
-	// 	});
-
-	// 	let texture = asset_server.get_texture("buddha16_5x2").unwrap();
-
-	// 	let texview = texture.create_view(&wgpu::TextureViewDescriptor {
-	// 		label: Some("sprite::BlitData._texview"),
-	// 		dimension: Some(wgpu::TextureViewDimension::D2),
-	// 		..Default::default()
-	// 	});
-
-	// 	let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
-
-	// 	let bind_group_layouts = [
-	// 		device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-	// 			label: Some("BlitData bind group layout 0: info buffer"),
-	// 			entries: &[
-	// 				wgpu::BindGroupLayoutEntry {
-	// 					binding: 0,
-	// 					visibility: wgpu::ShaderStages::VERTEX,
-	// 					ty: wgpu::BindingType::Buffer {
-	// 						ty: wgpu::BufferBindingType::Uniform,
-	// 						has_dynamic_offset: false,
-	// 						min_binding_size: None
-	// 					},
-	// 					count: None
-	// 				},
-	// 				wgpu::BindGroupLayoutEntry {
-	// 					binding: 1,
-	// 					visibility: wgpu::ShaderStages::VERTEX,
-	// 					ty: wgpu::BindingType::Buffer {
-	// 						ty: wgpu::BufferBindingType::Uniform,
-	// 						has_dynamic_offset: false,
-	// 						min_binding_size: None
-	// 					},
-	// 					count: None
-	// 				}
-	// 			]
-	// 		}),
-	// 		device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-	// 			label: Some("BlitData bind group layout 1: texture"),
-	// 			entries: &[
-	// 				wgpu::BindGroupLayoutEntry {
-	// 					binding: 0,
-	// 					visibility: wgpu::ShaderStages::FRAGMENT,
-	// 					ty: wgpu::BindingType::Texture {
-	// 						sample_type: wgpu::TextureSampleType::Float { filterable: true },
-	// 						view_dimension: wgpu::TextureViewDimension::D2,
-	// 						multisampled: false
-	// 					},
-	// 					count: None
-	// 				},
-	// 				wgpu::BindGroupLayoutEntry {
-	// 					binding: 1,
-	// 					visibility: wgpu::ShaderStages::FRAGMENT,
-	// 					ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
-	// 					count: None
-	// 				}
-	// 			]
-	// 		})
-	// 	];
-
-	// 	let bind_groups = [
-	// 		device.create_bind_group(&wgpu::BindGroupDescriptor {
-	// 			label: Some("sprite::BlitData.bind_groups[0]"),
-	// 			layout: &bind_group_layouts[0],
-	// 			entries: &[
-	// 				wgpu::BindGroupEntry {
-	// 					binding: 0,
-	// 					resource: surface_info.as_entire_binding()
-	// 				},
-	// 				wgpu::BindGroupEntry {
-	// 					binding: 1,
-	// 					resource: rect.as_entire_binding()
-	// 				}
-	// 			]
-	// 		}),
-	// 		device.create_bind_group(&wgpu::BindGroupDescriptor {
-	// 			label: Some("sprite::BlitData.bind_groups[1]"),
-	// 			layout: &bind_group_layouts[1],
-	// 			entries: &[
-	// 				wgpu::BindGroupEntry {
-	// 					binding: 0,
-	// 					resource: wgpu::BindingResource::TextureView(&texview)
-	// 				},
-	// 				wgpu::BindGroupEntry {
-	// 					binding: 1,
-	// 					resource: wgpu::BindingResource::Sampler(&sampler)
-	// 				}
-	// 			]
-	// 		})
-	// 	];
-
-	// 	let shader = asset_server.get_shader("texture_blit").unwrap();
-
-	// 	let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-	// 		label: Some("sprite BlitData pipeline layout"),
-	// 		bind_group_layouts: &[&bind_group_layouts[0], &bind_group_layouts[1]],
-	// 		push_constant_ranges: &[]
-	// 	});
-
-	// 	let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-	// 		label: Some("sprite::BlitData.pipeline"),
-	// 		layout: Some(&pipeline_layout),
-	// 		vertex: wgpu::VertexState {
-	// 			module: &shader,
-	// 			entry_point: "vs_main",
-	// 			buffers: &[],
-	// 			compilation_options: wgpu::PipelineCompilationOptions::default()
-	// 		},
-	// 		primitive: wgpu::PrimitiveState {
-	// 			topology: wgpu::PrimitiveTopology::TriangleStrip,
-	// 			strip_index_format: None,
-	// 			front_face: wgpu::FrontFace::Ccw,
-	// 			cull_mode: Some(wgpu::Face::Back),
-	// 			unclipped_depth: false,
-	// 			polygon_mode: wgpu::PolygonMode::Fill,
-	// 			conservative: false
-	// 		},
-	// 		depth_stencil: Some(wgpu::DepthStencilState {
-	// 			format: wgpu::TextureFormat::Depth32Float,
-	// 			depth_write_enabled: true,
-	// 			depth_compare: wgpu::CompareFunction::LessEqual,
-	// 			stencil: wgpu::StencilState::default(),
-	// 			bias: wgpu::DepthBiasState::default()
-	// 		}),
-	// 		multisample: wgpu::MultisampleState::default(),
-	// 		fragment: Some(wgpu::FragmentState {
-	// 			module: &shader,
-	// 			entry_point: "fs_main",
-	// 			targets: &[Some(wgpu::ColorTargetState {
-	// 				format: webgpu.get_config().format,
-	// 				blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-	// 				write_mask: wgpu::ColorWrites::all()
-	// 			})],
-	// 			compilation_options: wgpu::PipelineCompilationOptions::default()
-	// 		}),
-	// 		multiview: None,
-	// 		cache: None
-	// 	});
-
-	// 	Self {
-	// 		surface_info, rect, bind_groups, pipeline, _texview: texview, _sampler: sampler
-	// 	}
-	// }
 }
@@ -1,16 +1,15 @@
 
-use crate::{
-	webgpu::{WebGPU, WebGPUDevice, WebGPUConfig},
-	game::TileType
-};
+use wgpu::util::DeviceExt;
+use crate::webgpu::{WebGPU, WebGPUDevice, WebGPUConfig};
 
-use super::{SurfaceInfo, RaycastData, WallCameraInfo};
+use super::{SurfaceInfo, RaycastData, WallCameraInfo, WallTile};
 
 pub struct Data {
 	pub surface_info_buffer: wgpu::Buffer,
 	pub camera_info: wgpu::Buffer,
 	pub tilemap_data: wgpu::Buffer,
 	pub raycast_data_array_buffer: wgpu::Buffer,
+	pub texture_frames: wgpu::Buffer, // texture id -> current frame of the animated texture. Shared with floorceil.
 	_texture_view: wgpu::TextureView,
 	_texture_sampler: wgpu::Sampler,
 	pub compute_bind_group: wgpu::BindGroup,
@@ -22,6 +21,7 @@ pub struct Data {
 impl Data {
 	const MAX_RAYCOUNT: u64 = 4320; //8K
 	const MAX_TILESIZE: glam::U64Vec2 = glam::u64vec2(2048, 2048);
+	pub const MAX_TEXTURES: u32 = 1024;
 	const TILEMAP_FIELDS_DATA_SIZE: u64 = std::mem::size_of::<u32>() as u64 * 2 + std::mem::size_of::<f32>() as u64;
 }
 
@@ -42,7 +42,7 @@ impl Data {
 		});
 		let tilemap_data = device.create_buffer(&wgpu::BufferDescriptor {
 			label: Some("wall::Data.tilemap_data"),
-			size: std::mem::size_of::<TileType>() as u64 * Self::MAX_TILESIZE.x * Self::MAX_TILESIZE.y + Self::TILEMAP_FIELDS_DATA_SIZE,
+			size: std::mem::size_of::<WallTile>() as u64 * Self::MAX_TILESIZE.x * Self::MAX_TILESIZE.y + Self::TILEMAP_FIELDS_DATA_SIZE,
 			usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false
 		});
@@ -53,6 +53,12 @@ impl Data {
 			usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false
 		});
+		// Not animated textures are drawn as themselves.
+		let texture_frames = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("wall::Data.texture_frames"),
+			contents: bytemuck::cast_slice(&(0..Self::MAX_TEXTURES as i32).collect::<Vec<i32>>()),
+			usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST
+		});
 
 		let texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
 		
@@ -104,6 +110,16 @@ impl Data {
 						min_binding_size: None
 					},
 					count: None
+				},
+				wgpu::BindGroupLayoutEntry {
+					binding: 4,
+					visibility: wgpu::ShaderStages::COMPUTE,
+					ty: wgpu::BindingType::Buffer {
+						ty: wgpu::BufferBindingType::Storage { read_only: true },
+						has_dynamic_offset: false,
+						min_binding_size: None
+					},
+					count: None
 				}
 			]
 		});
@@ -127,6 +143,10 @@ impl Data {
 				wgpu::BindGroupEntry {
 					binding: 3,
 					resource: raycast_data_array_buffer.as_entire_binding()
+				},
+				wgpu::BindGroupEntry {
+					binding: 4,
+					resource: texture_frames.as_entire_binding()
 				}
 			]
 		});
@@ -279,7 +299,7 @@ impl Data {
 		Self {
 			surface_info_buffer, 
 			camera_info, tilemap_data,
-			raycast_data_array_buffer,
+			raycast_data_array_buffer, texture_frames,
 			_texture_view: texture_array_view,
 			_texture_sampler: texture_sampler,
 			render_bind_groups, render_pipeline,
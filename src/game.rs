@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 pub struct AABB {
 	pub left: f32, pub right: f32, pub top: f32, pub bottom: f32
 }
@@ -38,27 +40,127 @@ impl AABB {
 	}
 }
 
-#[derive(Copy, Clone)]
-pub enum TileType { Empty(u32, u32), Wall(u32) }
+mod mapfile;
+mod wolfmap;
+mod door;
+mod pushwall;
+mod statics;
+mod sprite;
+mod enemy;
+mod raycast;
+mod collision;
+mod entity;
+mod player;
+mod weapon;
+mod gate;
+mod demo;
+mod savegame;
+mod pathfind;
+mod area;
+mod tilechange;
+mod animation;
+
+pub use mapfile::{MapData, MapFileError};
+pub use door::{Door, DoorOrientation, DoorState};
+pub use pushwall::PushWall;
+pub use statics::{Static, Pickup};
+pub use sprite::DirectionalSprite;
+pub use enemy::{Enemy, EnemyState, EnemySpawn};
+pub use raycast::{RaycastHit, HitSide};
+pub use collision::{Contact, SweepResult};
+pub use entity::{Entities, Entity, EntityId, EntityKind, Transform, Collider, Sprite, Health};
+pub use player::{PlayerStats, PlayerState, PlayerInput};
+pub use gate::GateError;
+pub use demo::{Demo, DemoError};
+pub use pathfind::{PathFinder, Connectivity};
+pub use area::{Areas, Connection};
+pub use tilechange::{TileRect, TileDirty};
+pub use animation::{TextureAnimation, TextureAnimations};
+use tilechange::TileChanges;
+pub use savegame::{SaveError, SAVE_VERSION, SAVE_SLOTS, save_slot_path};
+pub use weapon::{AmmoType, WeaponDef, Weapons, ViewModelFrame, HitscanHit, WEAPONS};
+pub use wolfmap::{WolfMaps, WolfLevel, WolfImport, WolfObject, WolfObjectKind, WolfMapError};
 
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum TileType { Empty(u32, u32), Wall(WallFaces), Door(DoorOrientation, u32), PushWall(u32), Gate(u32, u32) } // Gate(texid, key id)
+
+impl TileType {
+	// Wall with the same texture on all faces.
+	pub const fn wall(texid: u32) -> Self {
+		TileType::Wall(WallFaces::all(texid))
+	}
+}
+
+// Texture ids of the faces of a wall tile. North is the face toward -y, like WolfLevel::player_start.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct WallFaces {
+	pub north: u32,
+	pub south: u32,
+	pub east: u32,
+	pub west: u32
+}
+
+impl WallFaces {
+	pub const fn all(texid: u32) -> Self {
+		Self { north: texid, south: texid, east: texid, west: texid }
+	}
+
+	pub fn is_uniform(&self) -> bool {
+		self.to_array().iter().all(|texid| *texid == self.north)
+	}
+
+	// north, south, east, west
+	pub fn to_array(&self) -> [u32; 4] {
+		[self.north, self.south, self.east, self.west]
+	}
+
+	pub fn from_array([north, south, east, west]: [u32; 4]) -> Self {
+		Self { north, south, east, west }
+	}
+
+	// Face which a ray going toward dir hits on the side.
+	pub fn get_face(&self, side: HitSide, dir: glam::Vec2) -> u32 {
+		match side {
+			HitSide::X if dir.x > 0.0 => self.west,
+			HitSide::X => self.east,
+			HitSide::Y if dir.y > 0.0 => self.north,
+			HitSide::Y => self.south
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
 pub struct TileMap {
-	pub data: Vec<TileType>,
+	data: Vec<TileType>, // changed only by set_tile() and resize() so the changes are tracked
 	pub width: u32,
 	pub height: u32,
-	pub grid_size: f32
+	pub grid_size: f32,
+	pub doors: BTreeMap<[u32; 2], Door>,
+	pub pushwalls: Vec<PushWall>, // moving push walls
+	pub animations: TextureAnimations,
+	areas: Option<Areas>, // rooms separated by doors and gates. None after set_tile() until get_areas() rebuilds them
+	changes: TileChanges
+}
+
+// The areas are made from the tiles, and the change log is the history of how they got there. Both are not compared.
+impl PartialEq for TileMap {
+	fn eq(&self, other: &Self) -> bool {
+		self.data == other.data && self.width == other.width && self.height == other.height && self.grid_size == other.grid_size
+			&& self.doors == other.doors && self.pushwalls == other.pushwalls && self.animations == other.animations
+	}
 }
 
 impl TileMap {
 	pub fn test_tilemap() -> Self {
 		const TEST_TILEMAP: [TileType; 64] = [
-		TileType::Wall(0), TileType::Wall(1), TileType::Wall(2), TileType::Wall(3), TileType::Wall(3), TileType::Wall(2), TileType::Wall(1), TileType::Wall(0), 
-		TileType::Wall(1), TileType::Empty(0, 1),   TileType::Empty(12, 13),   TileType::Empty(24, 25), TileType::Empty(6, 7),  TileType::Empty(8, 9),  TileType::Empty(10, 11), TileType::Wall(1),
-		TileType::Wall(2), TileType::Empty(2, 3),   TileType::Empty(14, 15),   TileType::Wall(0), 		TileType::Wall(1), 		TileType::Wall(2), 		TileType::Empty(7, 3),   TileType::Wall(2),
-		TileType::Wall(3), TileType::Empty(4, 5),   TileType::Empty(16, 17),   TileType::Empty(0, 0),   TileType::Empty(0, 0),  TileType::Wall(3), 		TileType::Empty(7, 3),   TileType::Wall(3),
-		TileType::Wall(3), TileType::Empty(6, 7),   TileType::Empty(18, 19),   TileType::Wall(0), 		TileType::Wall(1), 		TileType::Wall(2), 		TileType::Empty(7, 3),   TileType::Wall(3),
-		TileType::Wall(2), TileType::Empty(8, 9),   TileType::Empty(20, 21),   TileType::Empty(2, 5),   TileType::Empty(2, 5),  TileType::Empty(2, 5),  TileType::Empty(2, 5),   TileType::Wall(2),
-		TileType::Wall(1), TileType::Empty(10, 11), TileType::Empty(22, 23),   TileType::Empty(2, 5),   TileType::Empty(2, 5),  TileType::Empty(2, 5),  TileType::Empty(2, 5),   TileType::Wall(1),
-		TileType::Wall(0), TileType::Wall(1), TileType::Wall(2), TileType::Wall(3), TileType::Wall(3), TileType::Wall(2), TileType::Wall(1), TileType::Wall(0) 
+		TileType::wall(0), TileType::wall(1), TileType::wall(2), TileType::wall(3), TileType::wall(3), TileType::wall(2), TileType::wall(1), TileType::wall(0), 
+		TileType::wall(1), TileType::Empty(0, 1),   TileType::Empty(12, 13),   TileType::Empty(24, 25), TileType::Empty(6, 7),  TileType::Empty(8, 9),  TileType::Empty(10, 11), TileType::wall(1),
+		TileType::wall(2), TileType::Empty(2, 3),   TileType::Empty(14, 15),   TileType::wall(0), 		TileType::wall(1), 		TileType::wall(2), 		TileType::Door(DoorOrientation::Horizontal, 4),   TileType::wall(2),
+		TileType::wall(3), TileType::Empty(4, 5),   TileType::Empty(16, 17),   TileType::Empty(0, 0),   TileType::Empty(0, 0),  TileType::PushWall(3), 	TileType::Empty(7, 3),   TileType::wall(3),
+		TileType::wall(3), TileType::Empty(6, 7),   TileType::Empty(18, 19),   TileType::wall(0), 		TileType::wall(1), 		TileType::wall(2), 		TileType::Empty(7, 3),   TileType::wall(3),
+		TileType::wall(2), TileType::Empty(8, 9),   TileType::Empty(20, 21),   TileType::Empty(2, 5),   TileType::Empty(2, 5),  TileType::Empty(2, 5),  TileType::Empty(2, 5),   TileType::wall(2),
+		TileType::wall(1), TileType::Empty(10, 11), TileType::Empty(22, 23),   TileType::Empty(2, 5),   TileType::Empty(2, 5),  TileType::Empty(2, 5),  TileType::Empty(2, 5),   TileType::wall(1),
+		TileType::wall(0), TileType::wall(1), TileType::wall(2), TileType::wall(3), TileType::wall(3), TileType::wall(2), TileType::wall(1), TileType::wall(0) 
 		];
 
 		let width = 8;
@@ -66,16 +168,31 @@ impl TileMap {
 
 		let data = Vec::<TileType>::from(TEST_TILEMAP);
 
-		TileMap {
-			data, width, height,
-			grid_size: 100.0
-		}
+		TileMap::new(data, width, height, 100.0)
+	}
+
+	pub fn new(data: Vec<TileType>, width: u32, height: u32, grid_size: f32) -> Self {
+		let doors = data.iter().enumerate().filter_map(|(i, ty)| match ty {
+			TileType::Door(orientation, _) => Some(([i as u32 % width, i as u32 / width], Door::new(*orientation))),
+			_ => None
+		}).collect();
+		let mut tilemap = TileMap {
+			data, width, height, grid_size, doors, pushwalls: Vec::new(),
+			animations: TextureAnimations::default(), areas: None, changes: TileChanges::default()
+		};
+		tilemap.areas = Some(tilemap.compute_areas());
+		tilemap
 	}
 }
 
 
 impl TileMap {
 
+	// Tiles in rows. Use set_tile() to change them.
+	pub fn get_data(&self) -> &[TileType] {
+		&self.data
+	}
+
 	fn get_tile(&self, coord: glam::UVec2) -> Option<&TileType> {
 		if coord.x >= self.width || coord.y >= self.height {
 			return None;
@@ -83,55 +200,154 @@ impl TileMap {
 		self.data.get((coord.y * self.width + coord.x) as usize)
 	}
 
-	fn point_to_tile_coord(&self, point: glam::Vec2) -> glam::UVec2 {
-		(point / self.grid_size).round().as_uvec2()
-	}
-	
-	fn get_near_walls_coord_from(&self, point: glam::Vec2) -> Vec<glam::UVec2> {
-		let mut retval = Vec::<glam::UVec2>::new();
-		if point.x < 0.0 || point.y < 0.0 {
-			return retval;
+	// Coord of the tile which contains the point. None if the point is out of the map.
+	pub fn tile_coord_containing(&self, point: glam::Vec2) -> Option<glam::UVec2> {
+		let coord = (point / self.grid_size).floor();
+		if coord.x < 0.0 || coord.y < 0.0 || coord.x >= self.width as f32 || coord.y >= self.height as f32 {
+			return None;
 		}
-		let glam::UVec2 {x, y} = self.point_to_tile_coord(point);
-		
-		//(x,y) (x-1,y) (x,y-1) (x-1,y-1)
-		retval.push(glam::uvec2(x, y));
-		//NOTE: if문 생략해도 될까?
-		if x > 0 {
-			retval.push(glam::uvec2(x-1, y));
+		Some(coord.as_uvec2())
+	}
+
+	// Floor and ceiling texture ids of the tile.
+	// Door tile borrows them from the empty tile in front of it.
+	pub fn get_floor_ceil(&self, coord: glam::UVec2) -> Option<(u32, u32)> {
+		match self.get_tile(coord)? {
+			TileType::Empty(t1, t2) => Some((*t1, *t2)),
+			TileType::Wall(_) | TileType::PushWall(_) | TileType::Gate(_, _) => None,
+			TileType::Door(orientation, _) => {
+				let front = match orientation {
+					DoorOrientation::Vertical => [coord - glam::UVec2::X, coord + glam::UVec2::X],
+					DoorOrientation::Horizontal => [coord - glam::UVec2::Y, coord + glam::UVec2::Y]
+				};
+				front.into_iter().find_map(|c| match self.get_tile(c) {
+					Some(TileType::Empty(t1, t2)) => Some((*t1, *t2)),
+					_ => None
+				})
+			}
 		}
-		if y > 0 {
-			retval.push(glam::uvec2(x, y-1));
+	}
+
+	// AABB of the solid part of the tile in world space.
+	fn tile_aabb(&self, coord: glam::UVec2) -> Option<AABB> {
+		let offset = coord.as_vec2() * self.grid_size;
+		match self.get_tile(coord)? {
+			TileType::Empty(_, _) => None,
+			TileType::Wall(_) | TileType::PushWall(_) | TileType::Gate(_, _) => Some(AABB::from_rect(offset, self.grid_size, self.grid_size)),
+			TileType::Door(_, _) => {
+				let (min, max) = self.doors.get(&[coord.x, coord.y])?.slab_rect()?;
+				Some(AABB::from_rect(offset + min * self.grid_size, (max.x - min.x) * self.grid_size, (max.y - min.y) * self.grid_size))
+			}
 		}
-		if x > 0 && y > 0 {
-			retval.push(glam::uvec2(x-1, y-1))
+	}
+	
+	// AABBs of the solid tiles and the moving push walls overlapping the bounding box of the circle.
+	fn solid_aabbs_near(&self, position: glam::Vec2, radius: f32) -> impl Iterator<Item = AABB> + '_ {
+		let max_coord = glam::uvec2(self.width, self.height).saturating_sub(glam::UVec2::ONE);
+		let min = ((position - radius) / self.grid_size).floor().max(glam::Vec2::ZERO).as_uvec2().min(max_coord);
+		let max = ((position + radius) / self.grid_size).floor().max(glam::Vec2::ZERO).as_uvec2().min(max_coord);
+		(min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| glam::uvec2(x, y)))
+			.filter_map(|coord| self.tile_aabb(coord))
+			.chain(self.pushwalls.iter().map(|p| self.pushwall_aabb(p)))
+	}
+	pub fn circle_collision_check(&self, position: glam::Vec2, radius: f32) -> Option<AABB> {
+		self.solid_aabbs_near(position, radius).find(|aabb| aabb.circle_collision_check(position, radius))
+	}
+	pub fn circle_contacts(&self, position: glam::Vec2, radius: f32) -> Vec<Contact> {
+		self.solid_aabbs_near(position, radius).filter_map(|aabb| collision::circle_aabb_contact(position, radius, &aabb)).collect()
+	}
+
+	fn pushwall_aabb(&self, pushwall: &PushWall) -> AABB {
+		AABB::from_rect(pushwall.position() * self.grid_size, self.grid_size, self.grid_size)
+	}
+
+	// Start sliding the push wall at coord. Returns false if it is not a push wall or can't move.
+	pub fn push_wall(&mut self, coord: glam::UVec2, direction: glam::IVec2) -> bool {
+		let Some(&TileType::PushWall(texid)) = self.get_tile(coord) else {
+			return false;
+		};
+		let distance = (1..=PushWall::MAX_DISTANCE as i32)
+			.take_while(|i| {
+				let next = coord.as_ivec2() + direction * *i;
+				next.cmpge(glam::IVec2::ZERO).all() && matches!(self.get_tile(next.as_uvec2()), Some(TileType::Empty(_, _)))
+			})
+			.count() as u32;
+		if distance == 0 {
+			return false;
 		}
 
-		retval.into_iter().filter(|p| 
-			self.get_tile(*p).is_some_and(|f| 
-				match f {
-					TileType::Empty(_, _) => false,
-					TileType::Wall(_) => true
-				}
-		)).collect()
+		// The vacated tile takes the floor and ceiling of the tile where it is pushed from.
+		let behind = coord.as_ivec2() - direction;
+		let (t1, t2) = match behind.cmpge(glam::IVec2::ZERO).all() {
+			true => self.get_floor_ceil(behind.as_uvec2()).unwrap_or((0, 0)),
+			false => (0, 0)
+		};
+		self.set_tile(coord, TileType::Empty(t1, t2));
+		self.pushwalls.push(PushWall { origin: coord, direction, texid, distance, progress: 0.0 });
+		true
 	}
-	pub fn circle_collision_check(&self, position: glam::Vec2, radius: f32) -> Option<AABB> {
-		for wall_offset in self.get_near_walls_coord_from(position).into_iter().map(|f| f.as_vec2() * self.grid_size) {
-			let aabb = AABB::from_rect(wall_offset, self.grid_size, self.grid_size);
-			if aabb.circle_collision_check(position, radius) {
-				return Some(aabb);
+
+	// blocked: the block must not move into this area(e.g. the player).
+	pub fn tick_pushwalls(&mut self, delta: f32, blocked: impl Fn(&AABB) -> bool) {
+		for i in 0..self.pushwalls.len() {
+			let mut moved = self.pushwalls[i].clone();
+			moved.advance(delta);
+			if !blocked(&self.pushwall_aabb(&moved)) {
+				let (before, _) = self.pushwalls[i].covering_tiles();
+				let (first, second) = moved.covering_tiles();
+				self.mark_dirty(before.min(first), second.unwrap_or(first).max(before).max(first));
+				self.pushwalls[i] = moved;
 			}
 		}
-		None
+		let (finished, moving) = std::mem::take(&mut self.pushwalls).into_iter().partition(|p| p.is_finished());
+		self.pushwalls = moving;
+		for pushwall in finished {
+			let coord = pushwall.destination();
+			self.set_tile(coord, TileType::wall(pushwall.texid));
+		}
 	}
+
+	pub fn tick_doors(&mut self, delta: f32, blocked: impl Fn(glam::UVec2) -> bool) {
+		let mut changed = Vec::new();
+		for (coord, door) in self.doors.iter_mut() {
+			let open_amount = door.open_amount;
+			door.tick(delta, blocked(glam::UVec2::from_array(*coord)));
+			if door.open_amount != open_amount {
+				changed.push(glam::UVec2::from_array(*coord));
+			}
+		}
+		for coord in changed {
+			self.mark_dirty(coord, coord);
+			let open = self.doors.get(&coord.to_array()).is_some_and(|door| door.open_amount > 0.0);
+			if let Some(areas) = self.areas.as_mut() {
+				areas.set_open(coord, open);
+			}
+		}
+	}
+}
+
+// Things happened in the game world for HUD and audio. Taken by GameWorld::take_events.
+#[derive(Clone, PartialEq, Debug)]
+pub enum GameEvent {
+	PickedUp(Pickup),
+	GateOpened(glam::UVec2),
+	KeyRequired(u32) // used the locked gate without the key
 }
 
+#[derive(Clone, PartialEq, Debug)]
 pub struct GameWorld {
 	tilemap: TileMap,
-	player: Object,
-	//doors: BtreeMap<[u32;2], Door>
-	//statics: BtreeMap<[u32;2], Static>
-	//enemies: BtreeMap<[f32;2], Enemy>
+	entities: Entities,
+	player: EntityId,
+	player_stats: PlayerStats,
+	player_spawn: Transform,
+	previous_transforms: BTreeMap<EntityId, Transform>, // before the last step() for the interpolation
+	rng: u32,
+	secrets_found: u32,
+	secrets_total: u32,
+	time: f32, // seconds of the game time
+	events: Vec<GameEvent>,
+	pathfinder: PathFinder // routes chasing enemies around walls
 }
 
 impl GameWorld {
@@ -140,63 +356,395 @@ impl GameWorld {
 	}
 
 	pub fn test_gameworld() -> Self {
-		GameWorld {
-			tilemap: TileMap::test_tilemap(),
-			player: Object { angle: 0.0, position: glam::vec2(200.0, 200.0), radius: 25.0 },
+		Self::from_map(MapData::test_map())
+	}
+	pub fn from_map(map: MapData) -> Self {
+		let secrets_total = map.tilemap.data.iter().filter(|ty| matches!(ty, TileType::PushWall(_))).count() as u32;
+		let mut entities = Entities::default();
+		let player = entities.spawn(Entity::player(map.player_position, map.player_angle));
+		for (coord, object) in &map.statics {
+			entities.spawn(Entity::from_static(glam::UVec2::from_array(*coord), object, map.tilemap.grid_size));
+		}
+		for spawn in &map.enemies {
+			entities.spawn(Entity::guard(spawn));
 		}
+		entities.flush();
+		let mut gameworld = GameWorld {
+			tilemap: map.tilemap,
+			entities,
+			player,
+			player_stats: PlayerStats::default(),
+			player_spawn: Transform { position: map.player_position, angle: map.player_angle },
+			previous_transforms: BTreeMap::new(),
+			rng: 0x2545_f491,
+			secrets_found: 0,
+			secrets_total,
+			time: 0.0,
+			events: Vec::new(),
+			pathfinder: PathFinder::new(Connectivity::Eight)
+		};
+		gameworld.update_path_blocked();
+		gameworld
+	}
+	pub fn from_map_file(path: impl AsRef<std::path::Path>) -> Result<Self, MapFileError> {
+		MapData::load(path).map(Self::from_map)
 	}
 	pub fn get_walls(&self) -> std::collections::HashMap<glam::UVec2, u32> {
 		self.tilemap.data.iter().enumerate().filter_map(|(i, ty)| match ty {
-			TileType::Empty(_, _) => None,
-			TileType::Wall(id) => Some((
+			TileType::Empty(_, _) | TileType::Door(_, _) => None,
+			TileType::Wall(WallFaces { north: id, .. }) | TileType::PushWall(id) | TileType::Gate(id, _) => Some((
 				glam::uvec2(i as u32 % self.tilemap.width, i as u32 / self.tilemap.width), 
 				id.clone()
 			))
 		}).collect()
 	}
+	// Locked gates and their key ids.
+	pub fn get_gates(&self) -> std::collections::HashMap<glam::UVec2, u32> {
+		self.tilemap.data.iter().enumerate().filter_map(|(i, ty)| match ty {
+			TileType::Gate(_, key) => Some((glam::uvec2(i as u32 % self.tilemap.width, i as u32 / self.tilemap.width), *key)),
+			_ => None
+		}).collect()
+	}
+	pub fn get_time(&self) -> f32 {
+		self.time
+	}
 	pub fn get_grid_size(&self) -> f32 {
 		self.tilemap.grid_size
 	}
-	pub fn actors_position_angle_flatten(&self) -> Vec<[f32; 3]> {
-		Vec::<[f32; 3]>::from([[self.player.position.x, self.player.position.y, self.player.angle]])
+	pub fn get_entities(&self) -> &Entities {
+		&self.entities
+	}
+	pub fn get_player_id(&self) -> EntityId {
+		self.player
+	}
+	pub fn get_player(&self) -> &Entity {
+		self.entities.get(self.player).expect("player entity")
+	}
+	fn player_mut(&mut self) -> &mut Entity {
+		self.entities.get_mut(self.player).expect("player entity")
 	}
 	pub fn get_player_position(&self) -> glam::Vec2 {
-		self.player.position
+		self.get_player().transform.position
+	}
+	fn player_radius(&self) -> f32 {
+		self.get_player().collider.map_or(0.0, |c| c.radius)
+	}
+	// Events since the last call, in the order they happened.
+	pub fn take_events(&mut self) -> Vec<GameEvent> {
+		std::mem::take(&mut self.events)
+	}
+	// Walls, doors, push walls and blocking entities except the player.
+	pub fn circle_collision_check(&self, position: glam::Vec2, radius: f32) -> bool {
+		!self.contacts(position, radius, Some(self.player)).is_empty()
+	}
+	// Contacts with walls, doors, push walls and blocking entities except the mover.
+	fn contacts(&self, position: glam::Vec2, radius: f32, mover: Option<EntityId>) -> Vec<Contact> {
+		let entities = self.entities.iter()
+			.filter(|(id, entity)| Some(*id) != mover && entity.is_alive())
+			.filter_map(|(_, entity)| entity.collider.filter(|c| c.blocking).map(|c| (entity.transform.position, c.radius)))
+			.filter_map(|(center, other_radius)| collision::circle_circle_contact(position, radius, center, other_radius));
+		self.tilemap.circle_contacts(position, radius).into_iter().chain(entities).collect()
+	}
+	// Returns the position after moving from `from` to `to` with collision.
+	fn move_entity(&self, id: EntityId, from: glam::Vec2, to: glam::Vec2) -> glam::Vec2 {
+		let radius = self.entities.get(id).and_then(|e| e.collider).map_or(0.0, |c| c.radius);
+		collision::sweep_circle(from, to - from, radius, |position| self.contacts(position, radius, Some(id))).position
 	}
 	pub fn set_player_position(&mut self, pos: glam::Vec2) {
-		match self.tilemap.circle_collision_check(pos, self.player.radius) {
-			None => self.player.position = pos,
-			Some(_) => { // Try move along axis
-				let wishvec = pos - self.player.position;
-				let proj_x = wishvec.project_onto(glam::Vec2::X);
-				let proj_y = wishvec.project_onto(glam::Vec2::Y);
-
-				if self.tilemap.circle_collision_check(self.player.position + proj_x, self.player.radius).is_none() {
-					self.player.position += proj_x;
-				}
-				else if self.tilemap.circle_collision_check(self.player.position + proj_y, self.player.radius).is_none() {
-					self.player.position += proj_y;
-				}
-			}
-		}
+		let position = self.move_entity(self.player, self.get_player_position(), pos);
+		self.player_mut().transform.position = position;
 	}
+	// Player input is ignored while dead.
 	pub fn translate_player(&mut self, wishvec: glam::Vec2) {
-		self.set_player_position(self.player.position + wishvec);
+		if !self.player_stats.is_alive() {
+			return;
+		}
+		self.set_player_position(self.get_player_position() + wishvec);
 	}
 	pub fn rotate_player(&mut self, wishang: f32) {
-		self.player.angle += wishang;
+		if !self.player_stats.is_alive() {
+			return;
+		}
+		self.player_mut().transform.angle += wishang;
 	}
 	pub fn get_player_forward_vector(&self) -> glam::Vec2 {
-		glam::Vec2::from_angle(self.player.angle)
+		self.get_player().transform.forward()
+	}
+	pub fn get_doors(&self) -> impl Iterator<Item = (glam::UVec2, &Door)> {
+		self.tilemap.doors.iter().map(|(coord, door)| (glam::UVec2::from_array(*coord), door))
+	}
+
+	// Use(interact) the tile in front of the player. Returns true if something is used.
+	pub fn player_use(&mut self) -> bool {
+		if !self.player_stats.is_alive() {
+			return false;
+		}
+		let reach = self.player_radius() + self.tilemap.grid_size * 0.5;
+		let point = self.get_player_position() + self.get_player_forward_vector() * reach;
+		let Some(coord) = self.tilemap.tile_coord_containing(point) else {
+			return false;
+		};
+		if let Some(door) = self.tilemap.doors.get_mut(&[coord.x, coord.y]) {
+			door.use_door();
+			return true;
+		}
+		let from = self.tilemap.tile_coord_containing(self.get_player_position()).unwrap_or(coord);
+		let keys = &self.player_stats.keys;
+		match self.tilemap.unlock_gate(coord, from, |key| keys.contains(&key)) {
+			Ok(()) => {
+				self.events.push(GameEvent::GateOpened(coord));
+				return true;
+			},
+			Err(GateError::KeyMissing(key)) => {
+				self.events.push(GameEvent::KeyRequired(key));
+				return false;
+			},
+			Err(GateError::NotGate) => ()
+		}
+
+		// Push along the major axis of the player's facing.
+		let forward = self.get_player_forward_vector();
+		let direction = if forward.x.abs() > forward.y.abs() {
+			glam::ivec2(forward.x.signum() as i32, 0)
+		} else {
+			glam::ivec2(0, forward.y.signum() as i32)
+		};
+		if self.tilemap.push_wall(coord, direction) {
+			self.secrets_found += 1;
+			return true;
+		}
+		false
+	}
+
+	// (found, total)
+	pub fn get_secret_count(&self) -> (u32, u32) {
+		(self.secrets_found, self.secrets_total)
+	}
+
+	pub fn get_player_stats(&self) -> &PlayerStats {
+		&self.player_stats
+	}
+	pub fn get_player_health(&self) -> i32 {
+		self.get_player().health.map_or(0, |health| health.current)
+	}
+	pub fn is_game_over(&self) -> bool {
+		self.player_stats.state == PlayerState::GameOver
 	}
-}
 
+	// Damage the entity with health. source: world position the damage came from.
+	pub fn damage(&mut self, target: EntityId, amount: i32, source: Option<glam::Vec2>) {
+		let amount = if target == self.player {
+			if !self.player_stats.is_alive() {
+				return;
+			}
+			self.player_stats.absorb(amount)
+		} else {
+			amount
+		};
+		let Some(entity) = self.entities.get_mut(target) else {
+			return;
+		};
+		let Some(health) = entity.health.as_mut() else {
+			return;
+		};
+		health.current = (health.current - amount).max(0);
+		let health_left = health.current;
+		if let Some(ai) = entity.ai.as_mut() {
+			ai.hurt(health_left);
+			if let Some(source) = source {
+				ai.target = source;
+			}
+		}
+		if target == self.player && health_left == 0 {
+			self.player_stats.die(source);
+		}
+	}
+	pub fn damage_player(&mut self, amount: i32, source: Option<glam::Vec2>) {
+		self.damage(self.player, amount, source);
+	}
+
+	// xorshift32. Deterministic for the same sequence of ticks and inputs.
+	fn random_range(&mut self, min: i32, max: i32) -> i32 {
+		self.rng ^= self.rng << 13;
+		self.rng ^= self.rng >> 17;
+		self.rng ^= self.rng << 5;
+		min + (self.rng % (max - min + 1) as u32) as i32
+	}
+
+	// Death camera turns to the killer, then the player respawns at the start position of the level.
+	fn tick_player(&mut self, delta: f32) {
+		self.player_stats.weapons.tick(delta);
+		let transform = self.get_player().transform;
+		if let Some(to_killer) = self.player_stats.killer.map(|killer| killer - transform.position).filter(|v| *v != glam::Vec2::ZERO) {
+			let diff = transform.forward().angle_between(to_killer);
+			let turn = diff.signum() * (PlayerStats::DEATH_CAMERA_TURN_SPEED * delta).min(diff.abs());
+			self.player_mut().transform.angle += turn;
+		}
+		if self.player_stats.tick(delta) {
+			let spawn = self.player_spawn;
+			let player = self.player_mut();
+			player.transform = spawn;
+			if let Some(health) = player.health.as_mut() {
+				health.current = health.max;
+			}
+		}
+	}
+
+	// Wake the enemies in all the areas connected to the position through open doors.
+	pub fn make_noise(&mut self, position: glam::Vec2) {
+		let Some(coord) = self.tilemap.tile_coord_containing(position) else {
+			return;
+		};
+		let listeners: Vec<(EntityId, glam::UVec2)> = self.entities.iter()
+			.filter(|(_, entity)| entity.ai.is_some())
+			.filter_map(|(id, entity)| self.tilemap.tile_coord_containing(entity.transform.position).map(|coord| (id, coord)))
+			.collect();
+		let areas = self.tilemap.get_areas();
+		let heard = areas.get_connected(areas.get_areas_touching(coord));
+		for (id, coord) in listeners {
+			if !areas.get_areas_touching(coord).iter().any(|area| heard.contains(area)) {
+				continue;
+			}
+			if let Some(ai) = self.entities.get_mut(id).and_then(|entity| entity.ai.as_mut()) {
+				ai.alert(position);
+			}
+		}
+	}
+
+	// One fixed tick with the player input.
+	pub fn step(&mut self, input: &PlayerInput, delta: f32) {
+		self.previous_transforms = self.entities.iter().map(|(id, entity)| (id, entity.transform)).collect();
+
+		let wishdir = self.get_player_forward_vector().rotate((-glam::Vec2::Y).rotate(input.dir));
+		self.translate_player(wishdir * PlayerStats::MOVE_SPEED * delta);
+		self.rotate_player(-input.mouse_x.to_radians() * PlayerStats::MOUSE_SENSITIVITY * delta);
+		if input.use_pressed {
+			self.player_use();
+		}
+		if let Some(slot) = input.select_weapon {
+			self.player_select_weapon(slot);
+		}
+		self.player_fire(input.fire);
+		self.tick(delta);
+	}
 
-#[derive(Default)]
-struct Object {
-	position: glam::Vec2,
-	angle: f32,
-	radius: f32,
+	// Step the ticks holding the same input. use_pressed and select_weapon are applied only in the first tick.
+	pub fn step_ticks(&mut self, input: &PlayerInput, delta: f32, ticks: u32) {
+		let held = PlayerInput { use_pressed: false, select_weapon: None, ..input.clone() };
+		for tick in 0..ticks {
+			self.step(if tick == 0 { input } else { &held }, delta);
+		}
+	}
+
+	// Transform between the last two steps. alpha: 0 is the previous step, 1 is the current.
+	// Teleport(e.g. respawn) is not interpolated.
+	pub fn get_interpolated_transform(&self, id: EntityId, alpha: f32) -> Option<Transform> {
+		let current = self.entities.get(id)?.transform;
+		let Some(previous) = self.previous_transforms.get(&id).filter(|p| p.position.distance(current.position) < self.tilemap.grid_size) else {
+			return Some(current);
+		};
+		// Along the shorter arc. Angles from atan2 jump between +π and -π.
+		let turn = (current.angle - previous.angle + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI;
+		Some(Transform {
+			position: previous.position.lerp(current.position, alpha),
+			angle: previous.angle + turn * alpha
+		})
+	}
+
+	// Enemies don't open doors, and walk around blocking objects.
+	fn update_path_blocked(&mut self) {
+		let closed_doors = self.get_doors().filter(|(_, door)| door.state != DoorState::Open).map(|(coord, _)| coord);
+		let objects = self.entities.of_kind(EntityKind::Static)
+			.filter(|(_, e)| e.collider.is_some_and(|c| c.blocking))
+			.filter_map(|(_, e)| self.tilemap.tile_coord_containing(e.transform.position));
+		let blocked: Vec<glam::UVec2> = closed_doors.chain(objects).collect();
+		self.pathfinder.set_blocked(blocked);
+	}
+
+	// Center of the next tile to walk to the goal around walls, closed doors and blocking objects.
+	// None if the way to the goal is straight or the goal can't be reached.
+	fn next_waypoint(&mut self, from: glam::Vec2, goal: glam::Vec2) -> Option<glam::Vec2> {
+		let start = self.tilemap.tile_coord_containing(from)?;
+		let end = self.tilemap.tile_coord_containing(goal)?;
+		if self.pathfinder.line_of_sight(&self.tilemap, start, end) {
+			return None;
+		}
+		let path = self.pathfinder.find_path(&self.tilemap, start, end)?;
+		let path = self.pathfinder.smooth_path(&self.tilemap, &path);
+		path.get(1).map(|coord| (coord.as_vec2() + 0.5) * self.tilemap.grid_size)
+	}
+
+	pub fn tick(&mut self, delta: f32) {
+		self.time += delta;
+		self.entities.flush();
+		self.tick_player(delta);
+		let player_alive = self.player_stats.is_alive();
+		let (position, radius, grid_size) = (self.get_player_position(), self.player_radius(), self.tilemap.grid_size);
+
+		// Doors and push walls are blocked by the player and alive enemies.
+		let actors: Vec<(glam::Vec2, f32)> = self.entities.iter()
+			.filter(|(_, e)| matches!(e.kind, EntityKind::Player | EntityKind::Enemy) && e.is_alive())
+			.filter_map(|(_, e)| e.collider.map(|c| (e.transform.position, c.radius)))
+			.collect();
+		self.tilemap.tick_doors(delta, |coord| {
+			let aabb = AABB::from_rect(coord.as_vec2() * grid_size, grid_size, grid_size);
+			actors.iter().any(|(p, r)| aabb.circle_collision_check(*p, *r))
+		});
+		self.tilemap.tick_pushwalls(delta, |aabb| actors.iter().any(|(p, r)| aabb.circle_collision_check(*p, *r)));
+		self.update_path_blocked();
+
+		for id in self.entities.ids() {
+			let Some(mut entity) = self.entities.get(id).filter(|e| e.ai.is_some()).cloned() else {
+				continue;
+			};
+			let from = entity.transform.position;
+			let player_visible = (player_alive && self.tilemap.has_line_of_sight(from, position)).then_some(position);
+			let Some(ai) = entity.ai.as_mut() else {
+				continue;
+			};
+			let attacking = ai.state == EnemyState::Attack;
+			let waypoint = match ai.state {
+				EnemyState::Chase => self.next_waypoint(from, player_visible.unwrap_or(ai.target)),
+				_ => None
+			};
+			let wishvec = ai.think(&mut entity.transform, delta, grid_size, player_visible, waypoint);
+			if wishvec != glam::Vec2::ZERO {
+				let to = self.move_entity(id, from, from + wishvec);
+				entity.transform.position = to;
+				if to.distance(from) < wishvec.length() * 0.5 && ai.state == EnemyState::Patrol { // Turn around at the wall.
+					entity.transform.angle += std::f32::consts::PI;
+				}
+			}
+			// Attack is finished with the player in sight.
+			let hit = attacking && ai.state == EnemyState::Chase && player_visible.is_some();
+			if let Some(e) = self.entities.get_mut(id) {
+				*e = entity;
+			}
+			if hit {
+				self.damage_player(Enemy::ATTACK_DAMAGE, Some(from));
+			}
+		}
+
+		let pickups: Vec<(EntityId, Pickup)> = self.entities.in_area(position, radius + Static::RADIUS * grid_size)
+			.filter(|_| player_alive)
+			.filter_map(|(id, e)| e.pickup.filter(|_| {
+				let reach = radius + e.collider.map_or(0.0, |c| c.radius);
+				e.transform.position.distance_squared(position) < reach.powi(2)
+			}).map(|pickup| (id, pickup)))
+			.collect();
+		for (id, pickup) in pickups {
+			let Some(mut health) = self.get_player().health else {
+				break;
+			};
+			if !self.player_stats.take_pickup(pickup, &mut health) { // Stays in the world.
+				continue;
+			}
+			self.player_mut().health = Some(health);
+			self.entities.despawn(id);
+			self.events.push(GameEvent::PickedUp(pickup));
+		}
+		self.entities.flush();
+	}
 }
 
 
@@ -209,6 +757,206 @@ fn test_get_near_walls() {
 	assert!(tilemap.circle_collision_check(glam::vec2(60.0, 60.0), 50.0).is_some());
 }
 
+#[test]
+fn door_collision_respects_open_amount() {
+	let mut gameworld = GameWorld::test_gameworld();
+	// Horizontal door at (6, 2). Stand below it and look up(-y).
+	gameworld.player_mut().transform = Transform { position: glam::vec2(650.0, 340.0), angle: -std::f32::consts::FRAC_PI_2 };
+	let door_center = glam::vec2(650.0, 250.0);
+	assert!(gameworld.tilemap.circle_collision_check(door_center, 25.0).is_some());
+
+	assert!(gameworld.player_use());
+	gameworld.tick(0.5);
+	// Left half of the slab is opened.
+	assert!(gameworld.tilemap.circle_collision_check(glam::vec2(620.0, 250.0), 10.0).is_none());
+	assert!(gameworld.tilemap.circle_collision_check(glam::vec2(680.0, 250.0), 10.0).is_some());
+
+	gameworld.tick(0.5);
+	assert!(gameworld.tilemap.circle_collision_check(door_center, 25.0).is_none());
+
+	// Door doesn't close while the player is in the door tile.
+	gameworld.player_mut().transform.position = door_center;
+	gameworld.tick(Door::AUTO_CLOSE_TIME + 1.0);
+	gameworld.tick(1.0);
+	assert_eq!(gameworld.tilemap.doors[&[6, 2]].state, DoorState::Open);
+}
+
+#[test]
+fn pushwall_slides_two_tiles() {
+	let mut tilemap = TileMap::test_tilemap();
+	tilemap.set_tile(glam::uvec2(3, 3), TileType::PushWall(9)); // (2, 3) and (1, 3) are empty
+	let mut gameworld = GameWorld::from_map(MapData {
+		tilemap, player_position: glam::vec2(450.0, 350.0), player_angle: std::f32::consts::PI, statics: BTreeMap::new(), enemies: Vec::new()
+	});
+	assert_eq!(gameworld.get_secret_count(), (0, 2));
+
+	assert!(gameworld.player_use());
+	assert_eq!(gameworld.get_secret_count(), (1, 2));
+	assert!(!gameworld.player_use()); // Not a push wall anymore.
+
+	gameworld.tick(1.0); // half tile moved
+	let tilemap = gameworld.get_tilemap();
+	assert!(tilemap.circle_collision_check(glam::vec2(360.0, 350.0), 5.0).is_none());
+	assert!(tilemap.circle_collision_check(glam::vec2(340.0, 350.0), 5.0).is_some());
+
+	gameworld.tick(10.0);
+	let tilemap = gameworld.get_tilemap();
+	assert!(tilemap.pushwalls.is_empty());
+	assert_eq!(tilemap.data[3 * 8 + 1], TileType::wall(9));
+	assert_eq!(tilemap.data[3 * 8 + 3], TileType::Empty(0, 0));
+}
+
+#[test]
+fn statics_block_and_pickup() {
+	let mut gameworld = GameWorld::test_gameworld();
+	// Blocking decoration at (3, 5)
+	assert!(gameworld.circle_collision_check(glam::vec2(350.0, 590.0), 25.0));
+	assert!(gameworld.get_tilemap().circle_collision_check(glam::vec2(350.0, 590.0), 25.0).is_none());
+	assert!(!gameworld.circle_collision_check(gameworld.get_player_position(), 25.0)); // The player itself
+	gameworld.player_mut().transform.position = glam::vec2(250.0, 550.0);
+	gameworld.translate_player(glam::vec2(60.0, 0.0));
+	assert!((gameworld.get_player_position() - glam::vec2(300.0, 550.0)).length() < 1e-3); // Stopped touching it
+
+	// Treasure at (4, 3)
+	assert_eq!(gameworld.entities.of_kind(EntityKind::Static).count(), 2);
+	gameworld.player_mut().transform.position = glam::vec2(410.0, 350.0);
+	gameworld.tick(0.0);
+	assert_eq!(gameworld.take_events(), vec![GameEvent::PickedUp(Pickup::Treasure(100))]);
+	assert_eq!(gameworld.entities.of_kind(EntityKind::Static).count(), 1);
+	assert_eq!(gameworld.get_player_stats().score, 100);
+	assert!(gameworld.take_events().is_empty());
+
+	// Health pickup stays until the player is hurt.
+	gameworld.entities.spawn(Entity::from_static(glam::uvec2(4, 3), &Static::pickup(1, Pickup::Health(25)), 100.0));
+	gameworld.tick(0.0);
+	assert!(gameworld.take_events().is_empty());
+	gameworld.damage_player(10, None);
+	gameworld.tick(0.0);
+	assert_eq!(gameworld.take_events(), vec![GameEvent::PickedUp(Pickup::Health(25))]);
+	assert_eq!(gameworld.get_player_health(), PlayerStats::MAX_HEALTH);
+}
+
+#[test]
+fn locked_gate_needs_key() {
+	let mut gameworld = GameWorld::test_gameworld();
+	let gate = glam::uvec2(2, 3);
+	gameworld.tilemap.set_tile(gate, TileType::Gate(5, 1));
+	assert_eq!(gameworld.get_gates().get(&gate), Some(&1));
+	gameworld.player_mut().transform = Transform { position: glam::vec2(250.0, 250.0), angle: std::f32::consts::FRAC_PI_2 };
+
+	assert!(!gameworld.player_use());
+	assert_eq!(gameworld.take_events(), vec![GameEvent::KeyRequired(1)]);
+	gameworld.translate_player(glam::vec2(0.0, 100.0));
+	assert!((gameworld.get_player_position().y - 275.0).abs() < 1e-3);
+
+	gameworld.entities.spawn(Entity::from_static(glam::uvec2(2, 2), &Static::pickup(1, Pickup::Key(1)), 100.0));
+	gameworld.tick(0.0);
+	assert!(gameworld.get_player_stats().keys.contains(&1));
+	gameworld.take_events();
+	assert!(gameworld.player_use());
+	assert_eq!(gameworld.take_events(), vec![GameEvent::GateOpened(gate)]);
+	assert!(gameworld.get_gates().is_empty());
+	gameworld.translate_player(glam::vec2(0.0, 100.0));
+	assert!((gameworld.get_player_position().y - 375.0).abs() < 1e-3);
+}
+
+#[test]
+fn player_damage_death_and_respawn() {
+	let mut gameworld = GameWorld::test_gameworld();
+	let spawn = gameworld.get_player_position();
+
+	// Finished attack of the guard in sight hurts the player.
+	gameworld.player_mut().transform.position = glam::vec2(150.0, 150.0);
+	let guard_position = glam::vec2(350.0, 150.0);
+	let guard = gameworld.entities.spawn(Entity::guard(&EnemySpawn { position: guard_position, angle: std::f32::consts::PI, patrol: false }));
+	for _ in 0..9 {
+		gameworld.tick(0.1);
+	}
+	assert_eq!(gameworld.get_player_health(), PlayerStats::MAX_HEALTH - Enemy::ATTACK_DAMAGE);
+	gameworld.entities.despawn(guard);
+
+	gameworld.player_stats.armor = 20;
+	gameworld.damage_player(30, None);
+	assert_eq!(gameworld.get_player_health(), PlayerStats::MAX_HEALTH - Enemy::ATTACK_DAMAGE - 15);
+	assert_eq!(gameworld.get_player_stats().armor, 5);
+
+	// Death freezes the input and the camera turns to the killer.
+	gameworld.player_mut().transform.angle = std::f32::consts::PI;
+	gameworld.damage_player(1000, Some(guard_position));
+	assert_eq!(gameworld.get_player_health(), 0);
+	assert!(!gameworld.get_player().is_alive());
+	gameworld.translate_player(glam::vec2(100.0, 0.0));
+	assert_eq!(gameworld.get_player_position(), glam::vec2(150.0, 150.0));
+	assert!(!gameworld.player_use());
+	gameworld.tick(PlayerStats::DEATH_TIME * 0.5);
+	assert!(gameworld.get_player_forward_vector().dot(glam::Vec2::X) > 0.99);
+
+	gameworld.tick(PlayerStats::DEATH_TIME * 0.5);
+	assert!(gameworld.get_player_stats().is_alive());
+	assert_eq!(gameworld.get_player_position(), spawn);
+	assert_eq!(gameworld.get_player_health(), PlayerStats::MAX_HEALTH);
+	assert_eq!(gameworld.get_player_stats().lives, PlayerStats::START_LIVES - 1);
+
+	// Game over without lives left.
+	gameworld.player_stats.lives = 0;
+	gameworld.damage_player(1000, None);
+	gameworld.tick(PlayerStats::DEATH_TIME);
+	assert!(gameworld.is_game_over());
+	gameworld.tick(PlayerStats::DEATH_TIME);
+	assert!(gameworld.is_game_over());
+	assert_eq!(gameworld.get_player_health(), 0);
+}
+
+#[test]
+fn enemy_chases_visible_player() {
+	let mut gameworld = GameWorld::test_gameworld();
+	gameworld.player_mut().transform.position = glam::vec2(150.0, 150.0);
+	let angle = std::f32::consts::PI;
+	let chaser = gameworld.entities.spawn(Entity::guard(&EnemySpawn { position: glam::vec2(650.0, 150.0), angle, patrol: false }));
+	// Hidden behind the wall
+	let hidden = gameworld.entities.spawn(Entity::guard(&EnemySpawn { position: glam::vec2(450.0, 550.0), angle, patrol: false }));
+	let state = |gameworld: &GameWorld, id| gameworld.entities.get(id).unwrap().ai.as_ref().unwrap().state;
+
+	gameworld.tick(0.1);
+	assert_eq!(state(&gameworld, chaser), EnemyState::Chase);
+	assert_eq!(state(&gameworld, hidden), EnemyState::Stand);
+	for _ in 0..15 {
+		gameworld.tick(0.1);
+	}
+	assert!(gameworld.entities.get(chaser).unwrap().transform.position.x < 500.0);
+	assert_eq!(state(&gameworld, chaser), EnemyState::Attack);
+	assert_eq!(gameworld.entities.get(hidden).unwrap().transform.position, glam::vec2(450.0, 550.0));
+	assert_eq!(gameworld.entities.of_kind(EntityKind::Enemy).count(), 2);
+}
+
+#[test]
+fn enemy_chases_around_walls() {
+	let mut gameworld = GameWorld::test_gameworld();
+	gameworld.player_stats.state = PlayerState::GameOver; // Not seen, so the enemy goes to the noise.
+	// Below the wall at (4, 4), and the blocking object at (3, 5) is on the left.
+	let chaser = gameworld.entities.spawn(Entity::guard(&EnemySpawn { position: glam::vec2(450.0, 550.0), angle: 0.0, patrol: false }));
+	gameworld.entities.flush();
+	gameworld.entities.get_mut(chaser).unwrap().ai.as_mut().unwrap().alert(glam::vec2(450.0, 350.0));
+	for _ in 0..100 {
+		gameworld.tick(0.1);
+	}
+	let enemy = gameworld.entities.get(chaser).unwrap();
+	assert_eq!(enemy.ai.as_ref().unwrap().state, EnemyState::Stand);
+	assert!(enemy.transform.position.distance(glam::vec2(450.0, 350.0)) < 1e-3);
+}
+
+#[test]
+fn player_fast_move_slides_on_walls() {
+	let mut gameworld = GameWorld::test_gameworld();
+	gameworld.player_mut().transform.position = glam::vec2(150.0, 150.0);
+	gameworld.translate_player(glam::vec2(1000.0, 0.0));
+	assert!((gameworld.get_player_position() - glam::vec2(675.0, 150.0)).length() < 1e-3);
+
+	// Diagonal into the corner of the room
+	gameworld.translate_player(glam::vec2(1000.0, -1000.0));
+	assert!((gameworld.get_player_position() - glam::vec2(675.0, 125.0)).length() < 1e-3);
+}
+
 #[test]
 fn gameworld_walls_offset_test() {
 	let gameworld = GameWorld::test_gameworld();
@@ -216,4 +964,32 @@ fn gameworld_walls_offset_test() {
 	assert!(walls.get(&glam::uvec2(0, 0)).is_some());
 	assert!(walls.get(&glam::uvec2(1, 1)).is_none());
 	assert!(walls.get(&glam::uvec2(7, 7)).is_some());
-}
\ No newline at end of file
+}
+#[test]
+fn headless_step_ticks() {
+	// Only the public API, as headless users do.
+	let mut gameworld = GameWorld::test_gameworld();
+	let forward = PlayerInput { dir: glam::Vec2::Y, ..Default::default() };
+	gameworld.step_ticks(&forward, 1.0 / 60.0, 10);
+	assert!((gameworld.get_player_position() - glam::vec2(250.0, 200.0)).length() < 1e-2);
+
+	// Holding use opens the door once instead of toggling it every tick.
+	let mut gameworld = GameWorld::from_map(MapData {
+		player_position: glam::vec2(650.0, 340.0), player_angle: -std::f32::consts::FRAC_PI_2, ..MapData::test_map()
+	});
+	gameworld.step_ticks(&PlayerInput { use_pressed: true, ..Default::default() }, 1.0 / 60.0, 30);
+	let (_, door) = gameworld.get_doors().find(|(coord, _)| *coord == glam::uvec2(6, 2)).unwrap();
+	assert_eq!(door.state, DoorState::Opening);
+	assert!((door.open_amount - 0.5).abs() < 0.05);
+}
+
+#[test]
+fn interpolated_angle_takes_shorter_arc() {
+	let mut gameworld = GameWorld::test_gameworld();
+	let player = gameworld.get_player_id();
+	let position = gameworld.get_player_position();
+	gameworld.previous_transforms.insert(player, Transform { position, angle: std::f32::consts::PI - 0.1 });
+	gameworld.player_mut().transform.angle = -std::f32::consts::PI + 0.1;
+	let angle = gameworld.get_interpolated_transform(player, 0.5).unwrap().angle;
+	assert!((angle.rem_euclid(std::f32::consts::TAU) - std::f32::consts::PI).abs() < 1e-4);
+}
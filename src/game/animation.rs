@@ -0,0 +1,72 @@
+// Animated textures(water, lights, computer panels). A base texture id cycles through consecutive texture ids by game time.
+// Tiles keep the base id, and renderers replace it with the current frame, so the tiles don't change.
+
+use std::collections::BTreeMap;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TextureAnimation {
+	pub first: u32, // texture id of the first frame
+	pub count: u32,
+	pub frame_duration: f32 // seconds
+}
+
+impl TextureAnimation {
+	pub fn get_frame(&self, time: f32) -> u32 {
+		if self.count == 0 || self.frame_duration <= 0.0 {
+			return self.first;
+		}
+		self.first + (time / self.frame_duration).max(0.0) as u32 % self.count
+	}
+}
+
+// Base texture id -> animation
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TextureAnimations(BTreeMap<u32, TextureAnimation>);
+
+impl TextureAnimations {
+	pub fn insert(&mut self, texid: u32, animation: TextureAnimation) {
+		self.0.insert(texid, animation);
+	}
+
+	pub fn remove(&mut self, texid: u32) -> Option<TextureAnimation> {
+		self.0.remove(&texid)
+	}
+
+	pub fn get(&self, texid: u32) -> Option<&TextureAnimation> {
+		self.0.get(&texid)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (u32, &TextureAnimation)> {
+		self.0.iter().map(|(texid, animation)| (*texid, animation))
+	}
+
+	// Texture id to draw for the texture id at the time.
+	pub fn get_frame(&self, texid: u32, time: f32) -> u32 {
+		self.get(texid).map_or(texid, |animation| animation.get_frame(time))
+	}
+
+	// Current frames of all the animated texture ids.
+	pub fn frames_at(&self, time: f32) -> BTreeMap<u32, u32> {
+		self.iter().map(|(texid, animation)| (texid, animation.get_frame(time))).collect()
+	}
+}
+
+
+#[test]
+fn texture_animation_frames() {
+	let mut animations = TextureAnimations::default();
+	animations.insert(5, TextureAnimation { first: 10, count: 3, frame_duration: 0.5 });
+	assert_eq!(animations.get_frame(5, 0.0), 10);
+	assert_eq!(animations.get_frame(5, 0.49), 10);
+	assert_eq!(animations.get_frame(5, 0.5), 11);
+	assert_eq!(animations.get_frame(5, 1.2), 12);
+	assert_eq!(animations.get_frame(5, 1.5), 10); // loops
+	assert_eq!(animations.get_frame(4, 1.0), 4); // not animated
+	assert_eq!(animations.frames_at(0.75), BTreeMap::from([(5, 11)]));
+
+	// Broken animations stay at the first frame.
+	animations.insert(6, TextureAnimation { first: 7, count: 0, frame_duration: 0.5 });
+	animations.insert(8, TextureAnimation { first: 9, count: 2, frame_duration: 0.0 });
+	assert_eq!(animations.get_frame(6, 3.0), 7);
+	assert_eq!(animations.get_frame(8, 3.0), 9);
+}
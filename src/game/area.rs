@@ -0,0 +1,279 @@
+// Areas: empty tiles flood-filled into rooms which are separated by doors and gates.
+// Like Wolf3D, a noise is heard in all the areas connected through open doors.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{DoorOrientation, TileMap, TileType};
+
+// Door or gate tile between two areas.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Connection {
+	pub areas: (u32, u32),
+	pub open: bool
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Areas {
+	width: u32,
+	tile_areas: Vec<Option<u32>>, // None: not an empty tile
+	count: u32,
+	connections: BTreeMap<[u32; 2], Connection>
+}
+
+impl TileMap {
+	// Doors connect while they are not fully closed. Gates are closed. An unlocked gate is an empty tile.
+	pub fn compute_areas(&self) -> Areas {
+		let mut tile_areas = vec![None; self.data.len()];
+		let mut count = 0;
+		for start in 0..self.data.len() {
+			if tile_areas[start].is_some() || !matches!(self.data[start], TileType::Empty(_, _)) {
+				continue;
+			}
+			let mut stack = vec![start];
+			tile_areas[start] = Some(count);
+			while let Some(i) = stack.pop() {
+				let coord = glam::uvec2(i as u32 % self.width, i as u32 / self.width);
+				for next in neighbors(coord) {
+					let Some(TileType::Empty(_, _)) = self.get_tile(next) else {
+						continue;
+					};
+					let j = (next.y * self.width + next.x) as usize;
+					if tile_areas[j].is_none() {
+						tile_areas[j] = Some(count);
+						stack.push(j);
+					}
+				}
+			}
+			count += 1;
+		}
+
+		let mut areas = Areas { width: self.width, tile_areas, count, connections: BTreeMap::new() };
+		for (i, tile) in self.data.iter().enumerate() {
+			let coord = glam::uvec2(i as u32 % self.width, i as u32 / self.width);
+			let (sides, open) = match tile {
+				TileType::Door(orientation, _) => {
+					let open = self.doors.get(&coord.to_array()).is_some_and(|door| door.open_amount > 0.0);
+					(door_sides(coord, *orientation).to_vec(), open)
+				},
+				TileType::Gate(_, _) => (neighbors(coord).to_vec(), false),
+				_ => continue
+			};
+			let touching: BTreeSet<u32> = sides.into_iter().filter_map(|side| areas.get_area(side)).collect();
+			if let [a, b] = touching.into_iter().collect::<Vec<_>>()[..] {
+				areas.connections.insert(coord.to_array(), Connection { areas: (a, b), open });
+			}
+		}
+		areas
+	}
+
+	// Rebuilt here once after any number of set_tile(), so editing tiles doesn't flood-fill the map each time.
+	// The door connections are kept up to date by tick_doors().
+	pub fn get_areas(&mut self) -> &Areas {
+		if self.areas.is_none() {
+			self.areas = Some(self.compute_areas());
+		}
+		self.areas.as_ref().expect("areas are just computed")
+	}
+
+	// Open or close the door connections by the door states. Needed after the doors are changed directly.
+	pub(super) fn update_door_connections(&mut self) {
+		let Some(areas) = self.areas.as_mut() else {
+			return; // The rebuild reads the door states.
+		};
+		for (coord, door) in &self.doors {
+			areas.set_open(glam::UVec2::from_array(*coord), door.open_amount > 0.0);
+		}
+	}
+}
+
+// Negative coords wrap around and are out of the map.
+fn neighbors(coord: glam::UVec2) -> [glam::UVec2; 4] {
+	[coord + glam::UVec2::X, coord.wrapping_sub(glam::UVec2::X), coord + glam::UVec2::Y, coord.wrapping_sub(glam::UVec2::Y)]
+}
+
+// The tiles in front of and behind the door slab.
+fn door_sides(coord: glam::UVec2, orientation: DoorOrientation) -> [glam::UVec2; 2] {
+	let step = match orientation {
+		DoorOrientation::Vertical => glam::UVec2::X,
+		DoorOrientation::Horizontal => glam::UVec2::Y
+	};
+	[coord + step, coord.wrapping_sub(step)]
+}
+
+impl Areas {
+	pub fn get_count(&self) -> u32 {
+		self.count
+	}
+
+	pub fn get_area(&self, coord: glam::UVec2) -> Option<u32> {
+		if coord.x >= self.width {
+			return None;
+		}
+		*self.tile_areas.get((coord.y as usize).checked_mul(self.width as usize)? + coord.x as usize)?
+	}
+
+	// Area of the tile, or both areas of the connecting tile.
+	pub fn get_areas_touching(&self, coord: glam::UVec2) -> Vec<u32> {
+		match (self.get_area(coord), self.connections.get(&coord.to_array())) {
+			(Some(area), _) => vec![area],
+			(None, Some(connection)) => vec![connection.areas.0, connection.areas.1],
+			(None, None) => Vec::new()
+		}
+	}
+
+	pub fn get_connections(&self) -> impl Iterator<Item = (glam::UVec2, &Connection)> {
+		self.connections.iter().map(|(coord, connection)| (glam::UVec2::from_array(*coord), connection))
+	}
+
+	// Open or close the connecting tile. Returns false if it is not a connection.
+	pub fn set_open(&mut self, coord: glam::UVec2, open: bool) -> bool {
+		match self.connections.get_mut(&coord.to_array()) {
+			Some(connection) => {
+				connection.open = open;
+				true
+			},
+			None => false
+		}
+	}
+
+	// Areas next to the area through any connection, open or not.
+	pub fn get_adjacent(&self, area: u32) -> BTreeSet<u32> {
+		self.connections.values().filter_map(|connection| match connection.areas {
+			(a, b) if a == area => Some(b),
+			(a, b) if b == area => Some(a),
+			_ => None
+		}).collect()
+	}
+
+	// Areas reachable from the areas through open connections, including themselves.
+	pub fn get_connected(&self, areas: impl IntoIterator<Item = u32>) -> BTreeSet<u32> {
+		let mut connected: BTreeSet<u32> = areas.into_iter().collect();
+		let mut stack: Vec<u32> = connected.iter().copied().collect();
+		while let Some(area) = stack.pop() {
+			for connection in self.connections.values().filter(|connection| connection.open) {
+				let next = match connection.areas {
+					(a, b) if a == area => b,
+					(a, b) if b == area => a,
+					_ => continue
+				};
+				if connected.insert(next) {
+					stack.push(next);
+				}
+			}
+		}
+		connected
+	}
+}
+
+
+#[cfg(test)]
+fn three_rooms() -> TileMap {
+	// Room 0 | door | room 1 | gate | room 2
+	let (w, e) = (TileType::wall(0), TileType::Empty(0, 0));
+	let door = TileType::Door(DoorOrientation::Vertical, 4);
+	let gate = TileType::Gate(5, 1);
+	TileMap::new(vec![
+		w, w, w,    w, w, w,    w, w, w,
+		w, e, e, door, e, e, gate, e, w,
+		w, e, e,    w, e, e,    w, e, w,
+		w, w, w,    w, w, w,    w, w, w
+	], 9, 4, 100.0)
+}
+
+#[test]
+fn areas_flood_fill_and_connections() {
+	let mut tilemap = three_rooms();
+	let mut areas = tilemap.compute_areas();
+	assert_eq!(areas.get_count(), 3);
+	assert_eq!(areas.get_area(glam::uvec2(1, 1)), Some(0));
+	assert_eq!(areas.get_area(glam::uvec2(2, 2)), Some(0));
+	assert_eq!(areas.get_area(glam::uvec2(5, 2)), Some(1));
+	assert_eq!(areas.get_area(glam::uvec2(3, 1)), None);
+	assert_eq!(areas.get_areas_touching(glam::uvec2(3, 1)), vec![0, 1]);
+	assert_eq!(areas.get_adjacent(1), BTreeSet::from([0, 2]));
+
+	// Closed door and gate separate all rooms.
+	assert_eq!(areas.get_connected([0]), BTreeSet::from([0]));
+	assert!(areas.set_open(glam::uvec2(3, 1), true));
+	assert_eq!(areas.get_connected([0]), BTreeSet::from([0, 1]));
+	assert!(areas.set_open(glam::uvec2(6, 1), true));
+	assert_eq!(areas.get_connected([2]), BTreeSet::from([0, 1, 2]));
+	// Closing the connecting tile separates them again.
+	assert!(areas.set_open(glam::uvec2(3, 1), false));
+	assert_eq!(areas.get_connected([0]), BTreeSet::from([0]));
+	assert_eq!(areas.get_connected([1]), BTreeSet::from([1, 2]));
+	assert!(!areas.set_open(glam::uvec2(4, 1), true));
+
+	// Computed from the door state and the unlocked gate.
+	let computed = tilemap.compute_areas();
+	assert_eq!(tilemap.get_areas(), &computed);
+	tilemap.doors.get_mut(&[3, 1]).unwrap().open_amount = 0.5;
+	assert_eq!(tilemap.compute_areas().get_connected([0]), BTreeSet::from([0, 1]));
+	tilemap.unlock_gate(glam::uvec2(6, 1), glam::uvec2(5, 1), |_| true).unwrap();
+	let areas = tilemap.get_areas();
+	assert_eq!(areas.get_count(), 2);
+	assert_eq!(areas.get_area(glam::uvec2(7, 2)), areas.get_area(glam::uvec2(4, 1)));
+	let computed = tilemap.compute_areas();
+	assert_eq!(tilemap.get_areas(), &computed);
+
+	// Edits only drop the areas, and they are rebuilt once when asked.
+	tilemap.set_tile(glam::uvec2(4, 1), TileType::wall(0));
+	tilemap.set_tile(glam::uvec2(4, 2), TileType::wall(0));
+	assert!(tilemap.areas.is_none());
+	assert_eq!(tilemap.get_areas().get_count(), 2);
+	assert!(tilemap.areas.is_some());
+
+	// Door animation opens and closes the connection.
+	let mut tilemap = three_rooms();
+	tilemap.doors.get_mut(&[3, 1]).unwrap().use_door();
+	tilemap.tick_doors(0.1, |_| false);
+	assert_eq!(tilemap.get_areas().get_connected([0]), BTreeSet::from([0, 1]));
+	tilemap.doors.get_mut(&[3, 1]).unwrap().use_door();
+	tilemap.tick_doors(1.0, |_| false);
+	assert_eq!(tilemap.get_areas().get_connected([0]), BTreeSet::from([0]));
+
+	// Resizing keeps the open door connected.
+	tilemap.doors.get_mut(&[3, 1]).unwrap().use_door();
+	tilemap.tick_doors(0.1, |_| false);
+	tilemap.resize(10, 4, TileType::wall(0));
+	assert_eq!(tilemap.get_areas().get_connected([0]), BTreeSet::from([0, 1]));
+}
+
+#[test]
+fn noise_alerts_connected_areas() {
+	use super::{Entity, EnemySpawn, EnemyState, GameWorld, MapData};
+
+	let mut gameworld = GameWorld::from_map(MapData {
+		tilemap: three_rooms(),
+		player_position: glam::vec2(150.0, 150.0),
+		player_angle: 0.0,
+		statics: BTreeMap::new(),
+		enemies: vec![
+			// Facing away from the player
+			EnemySpawn { position: glam::vec2(550.0, 250.0), angle: 0.0, patrol: false },
+			EnemySpawn { position: glam::vec2(750.0, 250.0), angle: 0.0, patrol: false }
+		]
+	});
+	let state = |gameworld: &GameWorld, i: usize| {
+		let (_, entity) = gameworld.get_entities().iter().filter(|(_, entity)| entity.ai.is_some()).nth(i).unwrap();
+		entity.ai.as_ref().unwrap().state
+	};
+
+	// The door is closed.
+	assert!(gameworld.player_fire(true).is_some());
+	assert_eq!(state(&gameworld, 0), EnemyState::Stand);
+
+	gameworld.tilemap.doors.get_mut(&[3, 1]).unwrap().open_amount = 1.0;
+	gameworld.tilemap.update_door_connections();
+	gameworld.make_noise(glam::vec2(150.0, 150.0));
+	assert_eq!(state(&gameworld, 0), EnemyState::Chase);
+	assert_eq!(state(&gameworld, 1), EnemyState::Stand); // behind the gate
+
+	// Noise in the door tile is heard in both sides.
+	let guard = gameworld.entities.spawn(Entity::guard(&EnemySpawn { position: glam::vec2(150.0, 250.0), angle: 0.0, patrol: false }));
+	gameworld.entities.flush();
+	gameworld.tilemap.doors.get_mut(&[3, 1]).unwrap().open_amount = 0.0;
+	gameworld.tilemap.update_door_connections();
+	gameworld.make_noise(glam::vec2(350.0, 150.0));
+	assert_eq!(gameworld.get_entities().get(guard).unwrap().ai.as_ref().unwrap().state, EnemyState::Chase);
+}
@@ -0,0 +1,184 @@
+// Circle collision against AABBs and circles, and the swept move with wall sliding.
+
+use super::AABB;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Contact {
+	pub normal: glam::Vec2, // pointing out of the obstacle
+	pub penetration: f32
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SweepResult {
+	pub position: glam::Vec2,
+	pub contacts: Vec<Contact> // in the order they are met
+}
+
+// Touching is not a contact.
+pub fn circle_aabb_contact(position: glam::Vec2, radius: f32, aabb: &AABB) -> Option<Contact> {
+	let min = glam::vec2(aabb.left, aabb.bottom);
+	let max = glam::vec2(aabb.right, aabb.top);
+	let closest = position.clamp(min, max);
+	let diff = position - closest;
+	if diff != glam::Vec2::ZERO {
+		let distance = diff.length();
+		return (distance < radius).then(|| Contact { normal: diff / distance, penetration: radius - distance });
+	}
+
+	// Center is inside the box. Push out through the nearest edge.
+	let to_edges = [position.x - min.x, max.x - position.x, position.y - min.y, max.y - position.y];
+	let normals = [glam::Vec2::NEG_X, glam::Vec2::X, glam::Vec2::NEG_Y, glam::Vec2::Y];
+	let (i, distance) = to_edges.into_iter().enumerate().min_by(|a, b| a.1.total_cmp(&b.1))?;
+	Some(Contact { normal: normals[i], penetration: distance + radius })
+}
+
+pub fn circle_circle_contact(position: glam::Vec2, radius: f32, other: glam::Vec2, other_radius: f32) -> Option<Contact> {
+	let diff = position - other;
+	let distance = diff.length();
+	if distance >= radius + other_radius {
+		return None;
+	}
+	let normal = if distance > 0.0 { diff / distance } else { glam::Vec2::X };
+	Some(Contact { normal, penetration: radius + other_radius - distance })
+}
+
+// Distance along the normalized direction to where the ray enters the circle. 0 if the origin is inside.
+pub fn ray_circle_distance(origin: glam::Vec2, direction: glam::Vec2, center: glam::Vec2, radius: f32) -> Option<f32> {
+	let to_center = center - origin;
+	if to_center.length_squared() <= radius * radius {
+		return Some(0.0);
+	}
+	let along = to_center.dot(direction);
+	let closest_squared = to_center.length_squared() - along * along;
+	if along < 0.0 || closest_squared >= radius * radius {
+		return None;
+	}
+	Some(along - (radius * radius - closest_squared).sqrt())
+}
+
+// Move the circle along motion in substeps no longer than half of the radius, so it can't tunnel through thin walls.
+// contacts: all contacts of the circle at the position.
+// Each substep pushes the circle out along the contact normals, so only the motion along the wall is left(sliding).
+// The substep is at least MIN_STEP(a zero radius point) and there are at most MAX_STEPS(very long moves get longer substeps).
+pub fn sweep_circle(start: glam::Vec2, motion: glam::Vec2, radius: f32, contacts: impl Fn(glam::Vec2) -> Vec<Contact>) -> SweepResult {
+	const MAX_ITERATION: usize = 4;
+	const MIN_STEP: f32 = 1.0;
+	const MAX_STEPS: f32 = 1024.0;
+	const SLOP: f32 = 1e-3; // Float error after a push out is not a contact, it can be too small to move the position.
+	let contacts = |position: glam::Vec2| -> Vec<Contact> {
+		contacts(position).into_iter().filter(|contact| contact.penetration > SLOP).collect()
+	};
+	let step_length = (radius * 0.5).max(MIN_STEP);
+	let steps = (motion.length() / step_length).ceil().clamp(1.0, MAX_STEPS) as usize;
+	let step = motion / steps as f32;
+	let mut position = start;
+	let mut met = Vec::<Contact>::new();
+
+	for _ in 0..steps {
+		let previous = position;
+		position += step;
+		let mut resolved = false;
+		for _ in 0..MAX_ITERATION {
+			let Some(deepest) = contacts(position).into_iter().max_by(|a, b| a.penetration.total_cmp(&b.penetration)) else {
+				resolved = true;
+				break;
+			};
+			position += deepest.normal * deepest.penetration;
+			met.push(deepest);
+		}
+		if !resolved && !contacts(position).is_empty() { // Stuck between obstacles
+			position = previous;
+			break;
+		}
+	}
+	SweepResult { position, contacts: met }
+}
+
+
+#[test]
+fn collision_contacts() {
+	let aabb = AABB::from_rect(glam::vec2(100.0, 100.0), 100.0, 100.0);
+	let contact = circle_aabb_contact(glam::vec2(95.0, 150.0), 10.0, &aabb).unwrap();
+	assert_eq!(contact.normal, glam::Vec2::NEG_X);
+	assert!((contact.penetration - 5.0).abs() < 1e-4);
+	assert!(circle_aabb_contact(glam::vec2(90.0, 150.0), 10.0, &aabb).is_none());
+
+	// Corner gives a diagonal normal
+	let contact = circle_aabb_contact(glam::vec2(95.0, 95.0), 10.0, &aabb).unwrap();
+	assert!((contact.normal - glam::vec2(-1.0, -1.0).normalize()).length() < 1e-4);
+
+	// Center inside
+	let contact = circle_aabb_contact(glam::vec2(190.0, 150.0), 10.0, &aabb).unwrap();
+	assert_eq!(contact.normal, glam::Vec2::X);
+	assert!((contact.penetration - 20.0).abs() < 1e-4);
+
+	let contact = circle_circle_contact(glam::vec2(0.0, 15.0), 10.0, glam::Vec2::ZERO, 10.0).unwrap();
+	assert_eq!(contact.normal, glam::Vec2::Y);
+	assert!((contact.penetration - 5.0).abs() < 1e-4);
+
+	assert_eq!(ray_circle_distance(glam::Vec2::ZERO, glam::Vec2::X, glam::vec2(50.0, 0.0), 10.0), Some(40.0));
+	assert_eq!(ray_circle_distance(glam::Vec2::ZERO, glam::Vec2::X, glam::vec2(50.0, 10.0), 10.0), None);
+	assert_eq!(ray_circle_distance(glam::Vec2::ZERO, glam::Vec2::NEG_X, glam::vec2(50.0, 0.0), 10.0), None);
+	assert_eq!(ray_circle_distance(glam::vec2(45.0, 0.0), glam::Vec2::X, glam::vec2(50.0, 0.0), 10.0), Some(0.0));
+}
+
+#[test]
+fn sweep_high_speed_no_tunneling() {
+	let thin_wall = AABB::from_rect(glam::vec2(100.0, -1000.0), 5.0, 2000.0);
+	let contacts = |p: glam::Vec2| circle_aabb_contact(p, 10.0, &thin_wall).into_iter().collect();
+	let result = sweep_circle(glam::Vec2::ZERO, glam::vec2(1000.0, 0.0), 10.0, contacts);
+	assert!((result.position - glam::vec2(90.0, 0.0)).length() < 1e-3);
+	assert_eq!(result.contacts[0].normal, glam::Vec2::NEG_X);
+}
+
+#[test]
+fn sweep_slides_along_wall() {
+	let wall = AABB::from_rect(glam::vec2(100.0, -1000.0), 100.0, 2000.0);
+	let contacts = |p: glam::Vec2| circle_aabb_contact(p, 10.0, &wall).into_iter().collect();
+	let result = sweep_circle(glam::vec2(50.0, 0.0), glam::vec2(100.0, 100.0), 10.0, contacts);
+	assert!((result.position - glam::vec2(90.0, 100.0)).length() < 1e-3);
+}
+
+#[test]
+fn sweep_diagonal_into_corner() {
+	// Inside corner made of the right wall and the bottom wall.
+	let walls = [
+		AABB::from_rect(glam::vec2(100.0, -1000.0), 100.0, 2000.0),
+		AABB::from_rect(glam::vec2(-1000.0, 100.0), 2000.0, 100.0)
+	];
+	let contacts = |p: glam::Vec2| walls.iter().filter_map(|w| circle_aabb_contact(p, 10.0, w)).collect();
+	let result = sweep_circle(glam::vec2(50.0, 50.0), glam::vec2(200.0, 200.0), 10.0, contacts);
+	assert!((result.position - glam::vec2(90.0, 90.0)).length() < 1e-3);
+
+	// Outside corner: the circle slides around the corner of the box.
+	let block = AABB::from_rect(glam::vec2(100.0, 100.0), 100.0, 100.0);
+	let contacts = |p: glam::Vec2| circle_aabb_contact(p, 10.0, &block).into_iter().collect();
+	let result = sweep_circle(glam::vec2(50.0, 95.0), glam::vec2(200.0, 0.0), 10.0, contacts);
+	assert!(circle_aabb_contact(result.position, 10.0, &block).is_none());
+	assert!((result.position.y - 90.0).abs() < 1e-3);
+	assert!(result.position.x > 240.0); // A little bit is lost while being pushed back by the corner.
+}
+
+#[test]
+fn sweep_zero_radius_and_long_move() {
+	let wall = AABB::from_rect(glam::vec2(100.0, -1000.0), 100.0, 2000.0);
+	let contacts = |p: glam::Vec2| circle_aabb_contact(p, 0.0, &wall).into_iter().collect();
+	let result = sweep_circle(glam::Vec2::ZERO, glam::vec2(50.0, 0.0), 0.0, contacts);
+	assert_eq!(result.position, glam::vec2(50.0, 0.0));
+	let result = sweep_circle(glam::Vec2::ZERO, glam::vec2(1000.0, 0.0), 0.0, contacts);
+	assert!(result.position.x <= 100.0);
+
+	// Not a contact anywhere, so the steps are capped and it ends at the target.
+	let result = sweep_circle(glam::Vec2::ZERO, glam::vec2(1.0e9, 0.0), 10.0, |_| Vec::new());
+	assert!((result.position.x - 1.0e9).abs() < 1.0e5);
+}
+
+#[test]
+fn sweep_slides_around_corner_without_sticking() {
+	// The push out leaves a tiny penetration which doesn't move the position in f32.
+	let block = AABB::from_rect(glam::vec2(300.0, 400.0), 100.0, 100.0);
+	let contacts = |p: glam::Vec2| circle_aabb_contact(p, 25.0, &block).into_iter().collect();
+	let start = glam::vec2(282.89493, 381.7677);
+	let result = sweep_circle(start, (glam::vec2(450.0, 350.0) - start).normalize() * 15.0, 25.0, contacts);
+	assert!(result.position.x > start.x + 5.0);
+}
@@ -0,0 +1,219 @@
+/*
+
+Demo: the map and the player input of every tick. GameWorld ticks deterministically, so playing the inputs back
+from the map reproduces the same game.
+
+Plain-text demo file format.
+
+ - '#' starts a comment line. Blank lines are ignored.
+	demo <version>
+	delta <seconds per tick>
+	input <dir_x> <dir_y> <mouse_x> <use 0|1> <fire 0|1> <weapon slot|->
+		(one line per tick, in order)
+	map
+ - "map" line is followed by the map file(see mapfile.rs) until the end.
+
+ */
+
+use std::{fmt, path::Path, str::FromStr};
+
+use super::{GameWorld, MapData, MapFileError, PlayerInput};
+
+#[derive(Debug)]
+pub enum DemoError {
+	OpenFileFailed(std::io::Error),
+	WriteFileFailed(std::io::Error),
+	UnknownKey { line: usize, key: String },
+	InvalidValue { line: usize },
+	UnsupportedVersion(u32),
+	MissingField(&'static str),
+	Map(MapFileError) // line numbers are relative to the "map" line
+}
+
+impl fmt::Display for DemoError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			DemoError::OpenFileFailed(e) => write!(f, "failed to open demo file: {}", e),
+			DemoError::WriteFileFailed(e) => write!(f, "failed to write demo file: {}", e),
+			DemoError::UnknownKey { line, key } => write!(f, "line {}: unknown key '{}'", line, key),
+			DemoError::InvalidValue { line } => write!(f, "line {}: invalid value", line),
+			DemoError::UnsupportedVersion(version) => write!(f, "unsupported demo version {}", version),
+			DemoError::MissingField(field) => write!(f, "missing field '{}'", field),
+			DemoError::Map(e) => write!(f, "demo map: {}", e)
+		}
+	}
+}
+
+impl std::error::Error for DemoError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Demo {
+	pub map: MapData, // initial state
+	pub delta: f32, // seconds per tick
+	pub inputs: Vec<PlayerInput> // one per tick
+}
+
+impl Demo {
+	pub const VERSION: u32 = 1;
+
+	pub fn new(map: MapData, delta: f32) -> Self {
+		Self { map, delta, inputs: Vec::new() }
+	}
+
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, DemoError> {
+		match std::fs::read_to_string(path) {
+			Ok(s) => s.parse(),
+			Err(e) => Err(DemoError::OpenFileFailed(e))
+		}
+	}
+
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DemoError> {
+		std::fs::write(path, self.to_string()).map_err(DemoError::WriteFileFailed)
+	}
+
+	// Call with the input passed to GameWorld::step for every tick.
+	pub fn record(&mut self, input: &PlayerInput) {
+		self.inputs.push(input.clone());
+	}
+
+	// Game world at the start of the demo.
+	pub fn start(&self) -> GameWorld {
+		GameWorld::from_map(self.map.clone())
+	}
+
+	// Game world after all the recorded ticks.
+	pub fn play(&self) -> GameWorld {
+		let mut game_world = self.start();
+		for input in &self.inputs {
+			game_world.step(input, self.delta);
+		}
+		game_world
+	}
+}
+
+fn parse_single<T: FromStr>(line: usize, values: &[&str]) -> Result<T, DemoError> {
+	match values {
+		[value] => value.parse().map_err(|_| DemoError::InvalidValue { line }),
+		_ => Err(DemoError::InvalidValue { line })
+	}
+}
+
+fn parse_flag(line: usize, value: &str) -> Result<bool, DemoError> {
+	match value {
+		"0" => Ok(false),
+		"1" => Ok(true),
+		_ => Err(DemoError::InvalidValue { line })
+	}
+}
+
+fn parse_input(line: usize, values: &[&str]) -> Result<PlayerInput, DemoError> {
+	let [x, y, mouse_x, use_pressed, fire, select_weapon] = values else {
+		return Err(DemoError::InvalidValue { line });
+	};
+	let float = |value: &str| value.parse::<f32>().map_err(|_| DemoError::InvalidValue { line });
+	Ok(PlayerInput {
+		dir: glam::vec2(float(x)?, float(y)?),
+		mouse_x: float(mouse_x)?,
+		use_pressed: parse_flag(line, use_pressed)?,
+		fire: parse_flag(line, fire)?,
+		select_weapon: match *select_weapon {
+			"-" => None,
+			slot => Some(slot.parse().map_err(|_| DemoError::InvalidValue { line })?)
+		}
+	})
+}
+
+impl FromStr for Demo {
+	type Err = DemoError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut version: Option<u32> = None;
+		let mut delta: Option<f32> = None;
+		let mut inputs = Vec::<PlayerInput>::new();
+
+		let mut lines = s.lines().enumerate();
+		for (i, raw) in lines.by_ref() {
+			let line = i + 1;
+			let text = raw.trim();
+			if text.is_empty() || text.starts_with('#') {
+				continue;
+			}
+
+			let mut tokens = text.split_whitespace();
+			let key = tokens.next().unwrap_or_default();
+			let values: Vec<&str> = tokens.collect();
+			match key {
+				"demo" => {
+					let value = parse_single(line, &values)?;
+					if value != Self::VERSION {
+						return Err(DemoError::UnsupportedVersion(value));
+					}
+					version = Some(value);
+				},
+				"delta" => delta = Some(parse_single(line, &values)?),
+				"input" => inputs.push(parse_input(line, &values)?),
+				"map" if values.is_empty() => {
+					version.ok_or(DemoError::MissingField("demo"))?;
+					let delta = delta.ok_or(DemoError::MissingField("delta"))?;
+					let map_text: Vec<&str> = lines.map(|(_, raw)| raw).collect();
+					let map = map_text.join("\n").parse().map_err(DemoError::Map)?;
+					return Ok(Demo { map, delta, inputs });
+				},
+				_ => return Err(DemoError::UnknownKey { line, key: key.to_string() })
+			}
+		}
+		Err(DemoError::MissingField("map"))
+	}
+}
+
+impl fmt::Display for Demo {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "# Rulf 3D demo file")?;
+		writeln!(f, "demo {}", Self::VERSION)?;
+		writeln!(f, "delta {}", self.delta)?;
+		for input in &self.inputs {
+			write!(f, "input {} {} {} {} {} ", input.dir.x, input.dir.y, input.mouse_x, input.use_pressed as u8, input.fire as u8)?;
+			match input.select_weapon {
+				None => writeln!(f, "-")?,
+				Some(slot) => writeln!(f, "{}", slot)?
+			}
+		}
+		writeln!(f, "map")?;
+		write!(f, "{}", self.map)
+	}
+}
+
+
+#[test]
+fn demo_playback_reproduces_game() {
+	let mut demo = Demo::new(MapData::test_map(), 1.0 / 60.0);
+	let mut game_world = demo.start();
+	// Walk and turn in turns while shooting.
+	for tick in 0..180 {
+		let input = PlayerInput {
+			dir: if tick % 60 < 40 { glam::Vec2::Y } else { glam::Vec2::ZERO },
+			mouse_x: if (40..60).contains(&(tick % 60)) { -0.3 } else { 0.0 },
+			fire: tick % 30 == 0,
+			select_weapon: (tick == 90).then_some(0),
+			..Default::default()
+		};
+		demo.record(&input);
+		game_world.step(&input, demo.delta);
+	}
+	let recorded = game_world.get_player().transform;
+	assert!(recorded.position.distance(glam::vec2(200.0, 200.0)) > 100.0);
+
+	let parsed: Demo = demo.to_string().parse().unwrap();
+	assert_eq!(parsed, demo);
+	let played = parsed.play();
+	assert_eq!(played.get_player().transform, recorded);
+	assert_eq!(played.get_player_weapons().get_ammo(super::AmmoType::Bullet), game_world.get_player_weapons().get_ammo(super::AmmoType::Bullet));
+}
+
+#[test]
+fn demo_wrong_version() {
+	let text = "demo 99\ndelta 0.1\nmap\n";
+	assert!(matches!(text.parse::<Demo>(), Err(DemoError::UnsupportedVersion(99))));
+	let text = "demo 1\ndelta 0.1\ninput 0 1 0 0 2 -\nmap\n";
+	assert!(matches!(text.parse::<Demo>(), Err(DemoError::InvalidValue { line: 3 })));
+}
@@ -0,0 +1,111 @@
+// Sliding door placed at the middle of a TileType::Door tile.
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DoorOrientation {
+	Vertical,	// slab along y axis at the middle of the tile. Passing through x axis.
+	Horizontal	// slab along x axis at the middle of the tile. Passing through y axis.
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DoorState { Closed, Opening, Open, Closing }
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Door {
+	pub orientation: DoorOrientation,
+	pub state: DoorState,
+	pub open_amount: f32, // 0.0: closed, 1.0: fully opened
+	pub timer: f32 // seconds left before auto closing
+}
+
+impl Door {
+	pub const OPEN_SPEED: f32 = 1.0; // open_amount per second
+	pub const AUTO_CLOSE_TIME: f32 = 3.0;
+	pub const THICKNESS: f32 = 0.1; // ratio to grid size
+
+	pub fn new(orientation: DoorOrientation) -> Self {
+		Self { orientation, state: DoorState::Closed, open_amount: 0.0, timer: 0.0 }
+	}
+
+	pub fn use_door(&mut self) {
+		self.state = match self.state {
+			DoorState::Closed | DoorState::Closing => DoorState::Opening,
+			DoorState::Open | DoorState::Opening => DoorState::Closing
+		};
+	}
+
+	// blocked: something is in the door tile, so the door must not close.
+	pub fn tick(&mut self, delta: f32, blocked: bool) {
+		match self.state {
+			DoorState::Closed => (),
+			DoorState::Opening => {
+				self.open_amount += Self::OPEN_SPEED * delta;
+				if self.open_amount >= 1.0 {
+					self.open_amount = 1.0;
+					self.state = DoorState::Open;
+					self.timer = Self::AUTO_CLOSE_TIME;
+				}
+			},
+			DoorState::Open => {
+				self.timer -= delta;
+				if self.timer <= 0.0 {
+					if blocked {
+						self.timer = 0.0;
+					} else {
+						self.state = DoorState::Closing;
+					}
+				}
+			},
+			DoorState::Closing => {
+				if blocked { // Reopen like Wolf3D does.
+					self.state = DoorState::Opening;
+					return;
+				}
+				self.open_amount -= Self::OPEN_SPEED * delta;
+				if self.open_amount <= 0.0 {
+					self.open_amount = 0.0;
+					self.state = DoorState::Closed;
+				}
+			}
+		}
+	}
+
+	// Rect of the remaining slab in tile space(0.0~1.0) as (min, max). None if fully opened.
+	pub fn slab_rect(&self) -> Option<(glam::Vec2, glam::Vec2)> {
+		if self.open_amount >= 1.0 {
+			return None;
+		}
+		let half = Self::THICKNESS * 0.5;
+		match self.orientation {
+			DoorOrientation::Vertical => Some((glam::vec2(0.5 - half, self.open_amount), glam::vec2(0.5 + half, 1.0))),
+			DoorOrientation::Horizontal => Some((glam::vec2(self.open_amount, 0.5 - half), glam::vec2(1.0, 0.5 + half)))
+		}
+	}
+}
+
+
+#[test]
+fn door_open_and_auto_close() {
+	let mut door = Door::new(DoorOrientation::Vertical);
+	door.tick(1.0, false);
+	assert_eq!(door.state, DoorState::Closed);
+
+	door.use_door();
+	door.tick(0.5, false);
+	assert_eq!(door.state, DoorState::Opening);
+	assert_eq!(door.open_amount, 0.5);
+	door.tick(0.6, false);
+	assert_eq!(door.state, DoorState::Open);
+	assert_eq!(door.open_amount, 1.0);
+
+	door.tick(Door::AUTO_CLOSE_TIME, true);
+	assert_eq!(door.state, DoorState::Open); // blocked, wait
+	door.tick(0.1, false);
+	assert_eq!(door.state, DoorState::Closing);
+	door.tick(0.5, true);
+	assert_eq!(door.state, DoorState::Opening); // blocked while closing
+	door.tick(0.0, false);
+	door.use_door();
+	door.tick(2.0, false);
+	assert_eq!(door.state, DoorState::Closed);
+	assert_eq!(door.open_amount, 0.0);
+}
@@ -0,0 +1,169 @@
+// AI component of enemy entities driven by a state machine. GameWorld moves it and tells whether the player is visible.
+
+use super::Transform;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EnemyState { Stand, Patrol, Chase, Attack, Pain, Die, Dead }
+
+// Enemy placement in map data.
+#[derive(Clone, PartialEq, Debug)]
+pub struct EnemySpawn {
+	pub position: glam::Vec2,
+	pub angle: f32,
+	pub patrol: bool
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Enemy {
+	pub speed: f32, // tiles per second
+	pub state: EnemyState,
+	pub timer: f32, // seconds left in the current state(Attack, Pain, Die)
+	pub target: glam::Vec2 // last known player position
+}
+
+impl Enemy {
+	pub const ATTACK_TIME: f32 = 0.5;
+	pub const PAIN_TIME: f32 = 0.3;
+	pub const DIE_TIME: f32 = 0.8;
+	pub const ATTACK_RANGE: f32 = 3.0; // ratio to grid size
+	pub const ATTACK_DAMAGE: i32 = 10;
+
+	pub fn guard(patrol: bool) -> Self {
+		Self {
+			speed: 1.5,
+			state: if patrol { EnemyState::Patrol } else { EnemyState::Stand },
+			timer: 0.0,
+			target: glam::Vec2::ZERO
+		}
+	}
+
+	pub fn is_alive(&self) -> bool {
+		!matches!(self.state, EnemyState::Die | EnemyState::Dead)
+	}
+
+	// Called after the health component is reduced.
+	pub fn hurt(&mut self, health_left: i32) {
+		if !self.is_alive() {
+			return;
+		}
+		if health_left <= 0 {
+			self.state = EnemyState::Die;
+			self.timer = Self::DIE_TIME;
+		} else {
+			self.state = EnemyState::Pain;
+			self.timer = Self::PAIN_TIME;
+		}
+	}
+
+	// Heard a noise(e.g. a gunshot) at the position. Idle enemies go to check it.
+	pub fn alert(&mut self, position: glam::Vec2) {
+		if matches!(self.state, EnemyState::Stand | EnemyState::Patrol) {
+			self.state = EnemyState::Chase;
+			self.target = position;
+		}
+	}
+
+	// Standing or patrolling enemies notice the player only in front of them.
+	pub fn can_notice(&self, transform: &Transform, player_position: glam::Vec2) -> bool {
+		match self.state {
+			EnemyState::Stand | EnemyState::Patrol => glam::Vec2::from_angle(transform.angle).dot(player_position - transform.position) > 0.0,
+			_ => true
+		}
+	}
+
+	// Update the state and returns the wish movement(world space) for this tick.
+	// player_visible: Some(player position) if the line of sight to the player is clear.
+	// waypoint: where to walk first in Chase, when the target is behind walls.
+	pub fn think(&mut self, transform: &mut Transform, delta: f32, grid_size: f32, player_visible: Option<glam::Vec2>, waypoint: Option<glam::Vec2>) -> glam::Vec2 {
+		let step = self.speed * grid_size * delta;
+		match self.state {
+			EnemyState::Stand | EnemyState::Patrol => {
+				if let Some(player) = player_visible.filter(|player| self.can_notice(transform, *player)) {
+					self.state = EnemyState::Chase;
+					self.target = player;
+					return glam::Vec2::ZERO;
+				}
+				if self.state == EnemyState::Patrol {
+					return glam::Vec2::from_angle(transform.angle) * step;
+				}
+				glam::Vec2::ZERO
+			},
+			EnemyState::Chase => {
+				if let Some(player) = player_visible {
+					self.target = player;
+					if transform.position.distance(player) <= Self::ATTACK_RANGE * grid_size {
+						transform.face(player);
+						self.state = EnemyState::Attack;
+						self.timer = Self::ATTACK_TIME;
+						return glam::Vec2::ZERO;
+					}
+				}
+				let to_target = self.target - transform.position;
+				if to_target.length() <= step { // Reached the last known position, but the player is not there.
+					self.state = EnemyState::Stand;
+					return to_target;
+				}
+				let next = waypoint.unwrap_or(self.target);
+				transform.face(next);
+				(next - transform.position).normalize_or_zero() * step
+			},
+			EnemyState::Attack => {
+				if let Some(player) = player_visible {
+					transform.face(player);
+				}
+				self.timer -= delta;
+				if self.timer <= 0.0 { // GameWorld hurts the player on this transition.
+					self.state = EnemyState::Chase;
+				}
+				glam::Vec2::ZERO
+			},
+			EnemyState::Pain => {
+				self.timer -= delta;
+				if self.timer <= 0.0 {
+					self.state = EnemyState::Chase;
+				}
+				glam::Vec2::ZERO
+			},
+			EnemyState::Die => {
+				self.timer -= delta;
+				if self.timer <= 0.0 {
+					self.state = EnemyState::Dead;
+				}
+				glam::Vec2::ZERO
+			},
+			EnemyState::Dead => glam::Vec2::ZERO
+		}
+	}
+}
+
+
+#[test]
+fn enemy_state_transitions() {
+	let mut enemy = Enemy::guard(false);
+	let mut transform = Transform { position: glam::vec2(150.0, 150.0), angle: 0.0 };
+	let behind = glam::vec2(50.0, 150.0);
+	assert_eq!(enemy.think(&mut transform, 0.1, 100.0, Some(behind), None), glam::Vec2::ZERO);
+	assert_eq!(enemy.state, EnemyState::Stand); // Not noticed from behind
+
+	let far = glam::vec2(950.0, 150.0);
+	enemy.think(&mut transform, 0.1, 100.0, Some(far), None);
+	assert_eq!(enemy.state, EnemyState::Chase);
+	let wish = enemy.think(&mut transform, 0.1, 100.0, Some(far), None);
+	assert!((wish - glam::vec2(15.0, 0.0)).length() < 1e-4);
+
+	enemy.think(&mut transform, 0.1, 100.0, Some(glam::vec2(350.0, 150.0)), None);
+	assert_eq!(enemy.state, EnemyState::Attack);
+	enemy.think(&mut transform, Enemy::ATTACK_TIME, 100.0, None, None);
+	assert_eq!(enemy.state, EnemyState::Chase);
+
+	enemy.hurt(15);
+	assert_eq!(enemy.state, EnemyState::Pain);
+	enemy.think(&mut transform, Enemy::PAIN_TIME, 100.0, None, None);
+	assert_eq!(enemy.state, EnemyState::Chase);
+
+	enemy.hurt(0);
+	assert_eq!(enemy.state, EnemyState::Die);
+	assert!(!enemy.is_alive());
+	enemy.think(&mut transform, Enemy::DIE_TIME, 100.0, None, None);
+	assert_eq!(enemy.state, EnemyState::Dead);
+}
@@ -0,0 +1,207 @@
+// Entity storage. Every game object(player, enemies, statics) is an entity with optional components.
+
+use std::collections::BTreeMap;
+
+use super::{DirectionalSprite, Enemy, EnemySpawn, Pickup, PlayerStats, Static};
+
+// Ids are never reused, so an id stays valid(or missing) after despawning others.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct EntityId(pub u32);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum EntityKind { Player, Enemy, Static }
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Transform {
+	pub position: glam::Vec2, // world space
+	pub angle: f32
+}
+
+impl Transform {
+	pub fn forward(&self) -> glam::Vec2 {
+		glam::Vec2::from_angle(self.angle)
+	}
+
+	pub fn face(&mut self, point: glam::Vec2) {
+		let to_point = point - self.position;
+		self.angle = to_point.y.atan2(to_point.x);
+	}
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Collider {
+	pub radius: f32,
+	pub blocking: bool // false: only for overlap tests(e.g. pickups)
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Sprite {
+	Billboard(u32), // texture layer
+	Directional(DirectionalSprite)
+}
+
+impl Sprite {
+	// Texture layer to draw for the viewer.
+	pub fn layer(&self, transform: &Transform, viewer: glam::Vec2) -> u32 {
+		match self {
+			Sprite::Billboard(layer) => *layer,
+			Sprite::Directional(sprite) => sprite.layer(transform.position, transform.angle, viewer)
+		}
+	}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Health {
+	pub current: i32,
+	pub max: i32
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Entity {
+	pub kind: EntityKind,
+	pub transform: Transform,
+	pub collider: Option<Collider>,
+	pub sprite: Option<Sprite>,
+	pub health: Option<Health>,
+	pub ai: Option<Enemy>,
+	pub pickup: Option<Pickup>
+}
+
+impl Entity {
+	pub fn player(position: glam::Vec2, angle: f32) -> Self {
+		Self {
+			kind: EntityKind::Player,
+			transform: Transform { position, angle },
+			collider: Some(Collider { radius: 25.0, blocking: true }),
+			sprite: None,
+			health: Some(Health { current: PlayerStats::MAX_HEALTH, max: PlayerStats::MAX_HEALTH }),
+			ai: None,
+			pickup: None
+		}
+	}
+
+	pub fn guard(spawn: &EnemySpawn) -> Self {
+		Self {
+			kind: EntityKind::Enemy,
+			transform: Transform { position: spawn.position, angle: spawn.angle },
+			collider: Some(Collider { radius: 25.0, blocking: true }),
+			sprite: Some(Sprite::Directional(DirectionalSprite { base_layer: 2 })),
+			health: Some(Health { current: 25, max: 25 }),
+			ai: Some(Enemy::guard(spawn.patrol)),
+			pickup: None
+		}
+	}
+
+	// Static object at the center of the tile.
+	pub fn from_static(coord: glam::UVec2, object: &Static, grid_size: f32) -> Self {
+		Self {
+			kind: EntityKind::Static,
+			transform: Transform { position: (coord.as_vec2() + 0.5) * grid_size, angle: 0.0 },
+			collider: Some(Collider { radius: Static::RADIUS * grid_size, blocking: object.blocking }),
+			sprite: Some(Sprite::Billboard(object.texid)),
+			health: None,
+			ai: None,
+			pickup: object.pickup
+		}
+	}
+
+	// Out of health or dying enemies are not alive. Entities without health and AI are always alive.
+	pub fn is_alive(&self) -> bool {
+		self.health.is_none_or(|health| health.current > 0) && self.ai.as_ref().is_none_or(|ai| ai.is_alive())
+	}
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Entities {
+	next_id: u32,
+	entities: BTreeMap<EntityId, Entity>,
+	spawned: Vec<(EntityId, Entity)>,
+	despawned: Vec<EntityId>
+}
+
+impl Entities {
+	// Spawned and despawned entities are applied by flush(), so they can be requested while iterating during a tick.
+	pub fn spawn(&mut self, entity: Entity) -> EntityId {
+		let id = EntityId(self.next_id);
+		self.next_id += 1;
+		self.spawned.push((id, entity));
+		id
+	}
+
+	pub fn despawn(&mut self, id: EntityId) {
+		self.despawned.push(id);
+	}
+
+	pub fn flush(&mut self) {
+		self.entities.extend(self.spawned.drain(..));
+		for id in self.despawned.drain(..) {
+			self.entities.remove(&id);
+		}
+	}
+
+	// Entities with their ids, e.g. loaded from a save game. next_id must be greater than the ids.
+	pub fn restore(next_id: u32, entities: BTreeMap<EntityId, Entity>) -> Self {
+		Self { next_id, entities, spawned: Vec::new(), despawned: Vec::new() }
+	}
+
+	pub fn get_next_id(&self) -> u32 {
+		self.next_id
+	}
+
+	pub fn get(&self, id: EntityId) -> Option<&Entity> {
+		self.entities.get(&id)
+	}
+
+	pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+		self.entities.get_mut(&id)
+	}
+
+	pub fn len(&self) -> usize {
+		self.entities.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entities.is_empty()
+	}
+
+	// In the order of ids.
+	pub fn iter(&self) -> impl Iterator<Item = (EntityId, &Entity)> {
+		self.entities.iter().map(|(id, entity)| (*id, entity))
+	}
+
+	pub fn ids(&self) -> Vec<EntityId> {
+		self.entities.keys().copied().collect()
+	}
+
+	pub fn of_kind(&self, kind: EntityKind) -> impl Iterator<Item = (EntityId, &Entity)> {
+		self.iter().filter(move |(_, entity)| entity.kind == kind)
+	}
+
+	// Entities whose position is in the circle.
+	pub fn in_area(&self, center: glam::Vec2, radius: f32) -> impl Iterator<Item = (EntityId, &Entity)> {
+		self.iter().filter(move |(_, entity)| entity.transform.position.distance_squared(center) <= radius * radius)
+	}
+}
+
+
+#[test]
+fn entities_spawn_despawn_query() {
+	let mut entities = Entities::default();
+	let player = entities.spawn(Entity::player(glam::vec2(150.0, 150.0), 0.0));
+	let guard = entities.spawn(Entity::guard(&EnemySpawn { position: glam::vec2(450.0, 150.0), angle: 0.0, patrol: false }));
+	assert!(entities.get(player).is_none()); // Not flushed yet
+	entities.flush();
+	assert_eq!(entities.len(), 2);
+
+	let treasure = entities.spawn(Entity::from_static(glam::uvec2(2, 1), &Static::pickup(1, Pickup::Treasure(10)), 100.0));
+	entities.despawn(guard);
+	entities.flush();
+	assert!(entities.get(guard).is_none());
+	assert_eq!(entities.of_kind(EntityKind::Static).map(|(id, _)| id).collect::<Vec<_>>(), vec![treasure]);
+	assert_eq!(entities.in_area(glam::vec2(200.0, 150.0), 60.0).count(), 2);
+	assert_eq!(entities.in_area(glam::vec2(300.0, 150.0), 60.0).map(|(id, _)| id).collect::<Vec<_>>(), vec![treasure]);
+
+	// Ids are not reused.
+	let next = entities.spawn(Entity::player(glam::Vec2::ZERO, 0.0));
+	assert!(next > treasure && next != guard);
+}
@@ -0,0 +1,46 @@
+// Locked gate which is a solid TileType::Gate tile until the player uses it with the key.
+
+use super::{TileMap, TileType};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GateError {
+	NotGate,
+	KeyMissing(u32) // required key id
+}
+
+impl TileMap {
+	// Key id required to open the gate at coord.
+	pub fn get_gate_key(&self, coord: glam::UVec2) -> Option<u32> {
+		match self.get_tile(coord)? {
+			TileType::Gate(_, key) => Some(*key),
+			_ => None
+		}
+	}
+
+	// Open the gate at coord if one of the keys matches. The opened tile takes the floor and ceiling of the tile `from`.
+	pub fn unlock_gate(&mut self, coord: glam::UVec2, from: glam::UVec2, has_key: impl Fn(u32) -> bool) -> Result<(), GateError> {
+		let key = self.get_gate_key(coord).ok_or(GateError::NotGate)?;
+		if !has_key(key) {
+			return Err(GateError::KeyMissing(key));
+		}
+		let (t1, t2) = self.get_floor_ceil(from).unwrap_or((0, 0));
+		self.set_tile(coord, TileType::Empty(t1, t2));
+		Ok(())
+	}
+}
+
+
+#[test]
+fn gate_blocks_until_unlocked() {
+	let mut tilemap = TileMap::test_tilemap();
+	let gate = glam::uvec2(2, 3);
+	tilemap.set_tile(gate, TileType::Gate(5, 1));
+	assert!(tilemap.circle_collision_check(glam::vec2(250.0, 290.0), 25.0).is_some());
+	assert!(tilemap.raycast(glam::vec2(250.0, 250.0), glam::Vec2::Y, 1000.0).is_some_and(|hit| hit.coord == gate));
+
+	assert_eq!(tilemap.unlock_gate(gate, glam::uvec2(2, 2), |key| key == 0), Err(GateError::KeyMissing(1)));
+	assert_eq!(tilemap.unlock_gate(glam::uvec2(2, 2), glam::uvec2(2, 1), |_| true), Err(GateError::NotGate));
+	assert_eq!(tilemap.unlock_gate(gate, glam::uvec2(2, 2), |key| key == 1), Ok(()));
+	assert_eq!(tilemap.get_tile(gate), Some(&TileType::Empty(14, 15)));
+	assert!(tilemap.circle_collision_check(glam::vec2(250.0, 290.0), 25.0).is_none());
+}
@@ -0,0 +1,450 @@
+/*
+
+Plain-text map file format.
+
+ - '#' starts a comment line. Blank lines are ignored.
+ - Header lines are "<key> <values...>" and can come in any order before "tiles".
+	size <width> <height>
+	grid <grid_size>
+	player <x> <y> <angle(radian)>
+	static <tile_x> <tile_y> <texid> <block|pass> [health|ammo|treasure|key:<amount>]
+		(zero or more lines. e.g. "static 2 3 5 pass treasure:100")
+	enemy <x> <y> <angle(radian)> <stand|patrol>
+		(zero or more lines)
+	anim <texid> <first frame texid> <frame count> <frame seconds>
+		(zero or more lines. The texture id is drawn as the frames cycling by game time)
+ - "tiles" line is followed by exactly <height> rows of <width> whitespace separated tile codes.
+	W<texid>			TileType::Wall(WallFaces::all(texid))
+	W<n>/<s>/<e>/<w>	TileType::Wall(WallFaces { north, south, east, west }) (north is the face toward -y, like Wolf3D)
+	E<a>/<b>			TileType::Empty(a, b)
+	DV<texid>			TileType::Door(DoorOrientation::Vertical, texid)
+	DH<texid>			TileType::Door(DoorOrientation::Horizontal, texid)
+	P<texid>			TileType::PushWall(texid)
+	G<texid>/<key>		TileType::Gate(texid, key)
+
+Example)
+	size 3 3
+	grid 100
+	player 150 150 0
+	tiles
+	W0 W1   W0
+	W1 E0/1 W1
+	W0 W1   W0
+
+ */
+
+use std::{fmt, path::Path, str::FromStr};
+use std::collections::BTreeMap;
+
+use super::{TileMap, TileType, WallFaces, DoorOrientation, Static, Pickup, EnemySpawn, TextureAnimation, TextureAnimations};
+
+#[derive(Debug)]
+pub enum MapFileError {
+	OpenFileFailed(std::io::Error),
+	WriteFileFailed(std::io::Error),
+	UnknownKey { line: usize, key: String },
+	InvalidValue { line: usize },
+	MissingField(&'static str),
+	WrongRowLength { line: usize, expected: u32, found: usize },
+	WrongRowCount { expected: u32, found: usize },
+	UnknownTileCode { line: usize, column: usize, code: String }
+}
+
+impl fmt::Display for MapFileError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			MapFileError::OpenFileFailed(e) => write!(f, "failed to open map file: {}", e),
+			MapFileError::WriteFileFailed(e) => write!(f, "failed to write map file: {}", e),
+			MapFileError::UnknownKey { line, key } => write!(f, "line {}: unknown key '{}'", line, key),
+			MapFileError::InvalidValue { line } => write!(f, "line {}: invalid value", line),
+			MapFileError::MissingField(field) => write!(f, "missing field '{}'", field),
+			MapFileError::WrongRowLength { line, expected, found } => 
+				write!(f, "line {}: expected {} tiles in a row, found {}", line, expected, found),
+			MapFileError::WrongRowCount { expected, found } => 
+				write!(f, "expected {} tile rows, found {}", expected, found),
+			MapFileError::UnknownTileCode { line, column, code } => 
+				write!(f, "line {}, column {}: unknown tile code '{}'", line, column, code)
+		}
+	}
+}
+
+impl std::error::Error for MapFileError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapData {
+	pub tilemap: TileMap,
+	pub player_position: glam::Vec2,
+	pub player_angle: f32,
+	pub statics: BTreeMap<[u32; 2], Static>,
+	pub enemies: Vec<EnemySpawn>
+}
+
+impl MapData {
+	pub fn test_map() -> Self {
+		MapData {
+			tilemap: TileMap::test_tilemap(),
+			player_position: glam::vec2(200.0, 200.0),
+			player_angle: 0.0,
+			statics: BTreeMap::from([
+				([3, 5], Static::decoration(0, true)),
+				([4, 3], Static::pickup(1, Pickup::Treasure(100)))
+			]),
+			enemies: Vec::new()
+		}
+	}
+
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, MapFileError> {
+		match std::fs::read_to_string(path) {
+			Ok(s) => s.parse(),
+			Err(e) => Err(MapFileError::OpenFileFailed(e))
+		}
+	}
+
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MapFileError> {
+		std::fs::write(path, self.to_string()).map_err(MapFileError::WriteFileFailed)
+	}
+}
+
+fn parse_values<T: FromStr, const N: usize>(line: usize, values: &[&str]) -> Result<[T; N], MapFileError> {
+	if values.len() != N {
+		return Err(MapFileError::InvalidValue { line });
+	}
+	let parsed: Vec<T> = values.iter()
+		.map(|v| v.parse::<T>().map_err(|_| MapFileError::InvalidValue { line }))
+		.collect::<Result<_, _>>()?;
+	parsed.try_into().map_err(|_| MapFileError::InvalidValue { line })
+}
+
+pub(super) fn parse_tile(code: &str) -> Option<TileType> {
+	if let Some(texids) = code.strip_prefix('W') {
+		if let Ok(texid) = texids.parse() {
+			return Some(TileType::wall(texid));
+		}
+		let faces: Vec<u32> = texids.split('/').map(|texid| texid.parse().ok()).collect::<Option<_>>()?;
+		return faces.try_into().ok().map(|faces| TileType::Wall(WallFaces::from_array(faces)));
+	}
+	if let Some(pair) = code.strip_prefix('E') {
+		let (a, b) = pair.split_once('/')?;
+		return Some(TileType::Empty(a.parse().ok()?, b.parse().ok()?));
+	}
+	if let Some(texid) = code.strip_prefix('P') {
+		return texid.parse().ok().map(TileType::PushWall);
+	}
+	if let Some(pair) = code.strip_prefix('G') {
+		let (texid, key) = pair.split_once('/')?;
+		return Some(TileType::Gate(texid.parse().ok()?, key.parse().ok()?));
+	}
+	if let Some(texid) = code.strip_prefix("DV") {
+		return texid.parse().ok().map(|t| TileType::Door(DoorOrientation::Vertical, t));
+	}
+	if let Some(texid) = code.strip_prefix("DH") {
+		return texid.parse().ok().map(|t| TileType::Door(DoorOrientation::Horizontal, t));
+	}
+	None
+}
+
+pub(super) fn tile_code(tile: &TileType) -> String {
+	match tile {
+		TileType::Wall(faces) if faces.is_uniform() => format!("W{}", faces.north),
+		TileType::Wall(faces) => format!("W{}", faces.to_array().map(|texid| texid.to_string()).join("/")),
+		TileType::Empty(a, b) => format!("E{}/{}", a, b),
+		TileType::Door(DoorOrientation::Vertical, texid) => format!("DV{}", texid),
+		TileType::Door(DoorOrientation::Horizontal, texid) => format!("DH{}", texid),
+		TileType::PushWall(texid) => format!("P{}", texid),
+		TileType::Gate(texid, key) => format!("G{}/{}", texid, key)
+	}
+}
+
+// "<health|ammo|treasure|key>:<amount>"
+pub(super) fn parse_pickup(code: &str) -> Option<Pickup> {
+	let (kind, amount) = code.split_once(':')?;
+	let amount = amount.parse().ok()?;
+	match kind {
+		"health" => Some(Pickup::Health(amount)),
+		"ammo" => Some(Pickup::Ammo(amount)),
+		"treasure" => Some(Pickup::Treasure(amount)),
+		"key" => Some(Pickup::Key(amount)),
+		_ => None
+	}
+}
+
+pub(super) fn pickup_code(pickup: Pickup) -> String {
+	match pickup {
+		Pickup::Health(n) => format!("health:{}", n),
+		Pickup::Ammo(n) => format!("ammo:{}", n),
+		Pickup::Treasure(n) => format!("treasure:{}", n),
+		Pickup::Key(n) => format!("key:{}", n)
+	}
+}
+
+// "<texid> <first frame texid> <frame count> <frame seconds>"
+pub(super) fn parse_animation(values: &[&str]) -> Option<(u32, TextureAnimation)> {
+	let [texid, first, count, frame_duration] = values else {
+		return None;
+	};
+	let animation = TextureAnimation { first: first.parse().ok()?, count: count.parse().ok()?, frame_duration: frame_duration.parse().ok()? };
+	Some((texid.parse().ok()?, animation))
+}
+
+pub(super) fn animation_code(texid: u32, animation: &TextureAnimation) -> String {
+	format!("{} {} {} {}", texid, animation.first, animation.count, animation.frame_duration)
+}
+
+fn parse_static(line: usize, values: &[&str]) -> Result<([u32; 2], Static), MapFileError> {
+	if values.len() < 4 || values.len() > 5 {
+		return Err(MapFileError::InvalidValue { line });
+	}
+	let [x, y, texid] = parse_values::<u32, 3>(line, &values[..3])?;
+	let blocking = match values[3] {
+		"block" => true,
+		"pass" => false,
+		_ => return Err(MapFileError::InvalidValue { line })
+	};
+	let pickup = match values.get(4) {
+		None => None,
+		Some(pickup) => Some(parse_pickup(pickup).ok_or(MapFileError::InvalidValue { line })?)
+	};
+	Ok(([x, y], Static { texid, blocking, pickup }))
+}
+
+fn parse_enemy(line: usize, values: &[&str]) -> Result<EnemySpawn, MapFileError> {
+	if values.len() != 4 {
+		return Err(MapFileError::InvalidValue { line });
+	}
+	let [x, y, angle] = parse_values::<f32, 3>(line, &values[..3])?;
+	let patrol = match values[3] {
+		"stand" => false,
+		"patrol" => true,
+		_ => return Err(MapFileError::InvalidValue { line })
+	};
+	Ok(EnemySpawn { position: glam::vec2(x, y), angle, patrol })
+}
+
+impl FromStr for MapData {
+	type Err = MapFileError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut size: Option<[u32; 2]> = None;
+		let mut grid_size: Option<f32> = None;
+		let mut player: Option<[f32; 3]> = None;
+		let mut rows: Option<Vec<(usize, &str)>> = None;
+		let mut statics = BTreeMap::<[u32; 2], Static>::new();
+		let mut enemies = Vec::<EnemySpawn>::new();
+		let mut animations = TextureAnimations::default();
+
+		for (i, raw) in s.lines().enumerate() {
+			let line = i + 1;
+			let text = raw.trim();
+			if text.is_empty() || text.starts_with('#') {
+				continue;
+			}
+			if let Some(rows) = rows.as_mut() {
+				rows.push((line, text));
+				continue;
+			}
+
+			let mut tokens = text.split_whitespace();
+			let key = tokens.next().unwrap_or_default();
+			let values: Vec<&str> = tokens.collect();
+			match key {
+				"size" => {
+					let [width, height] = parse_values::<u32, 2>(line, &values)?;
+					width.checked_mul(height).ok_or(MapFileError::InvalidValue { line })?;
+					size = Some([width, height]);
+				},
+				"grid" => {
+					let [grid] = parse_values::<f32, 1>(line, &values)?;
+					if !grid.is_finite() || grid <= 0.0 {
+						return Err(MapFileError::InvalidValue { line });
+					}
+					grid_size = Some(grid);
+				},
+				"player" => player = Some(parse_values(line, &values)?),
+				"static" => {
+					let (coord, object) = parse_static(line, &values)?;
+					statics.insert(coord, object);
+				},
+				"enemy" => enemies.push(parse_enemy(line, &values)?),
+				"anim" => {
+					let (texid, animation) = parse_animation(&values).ok_or(MapFileError::InvalidValue { line })?;
+					animations.insert(texid, animation);
+				},
+				"tiles" if values.is_empty() => rows = Some(Vec::new()),
+				_ => return Err(MapFileError::UnknownKey { line, key: key.to_string() })
+			}
+		}
+
+		let [width, height] = size.ok_or(MapFileError::MissingField("size"))?;
+		let grid_size = grid_size.ok_or(MapFileError::MissingField("grid"))?;
+		let [x, y, angle] = player.ok_or(MapFileError::MissingField("player"))?;
+		let rows = rows.ok_or(MapFileError::MissingField("tiles"))?;
+
+		if rows.len() != height as usize {
+			return Err(MapFileError::WrongRowCount { expected: height, found: rows.len() });
+		}
+
+		// Grows by the rows checked, so a huge size in a broken header doesn't allocate the whole map up front.
+		let mut data = Vec::<TileType>::new();
+		for (line, row) in rows {
+			let codes: Vec<&str> = row.split_whitespace().collect();
+			if codes.len() != width as usize {
+				return Err(MapFileError::WrongRowLength { line, expected: width, found: codes.len() });
+			}
+			for (column, code) in codes.into_iter().enumerate() {
+				match parse_tile(code) {
+					Some(tile) => data.push(tile),
+					None => return Err(MapFileError::UnknownTileCode { line, column, code: code.to_string() })
+				}
+			}
+		}
+
+		let mut tilemap = TileMap::new(data, width, height, grid_size);
+		tilemap.animations = animations;
+		Ok(MapData {
+			tilemap,
+			player_position: glam::vec2(x, y),
+			player_angle: angle,
+			statics,
+			enemies
+		})
+	}
+}
+
+impl fmt::Display for MapData {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let tilemap = &self.tilemap;
+		writeln!(f, "# Rulf 3D map file")?;
+		writeln!(f, "size {} {}", tilemap.width, tilemap.height)?;
+		writeln!(f, "grid {}", tilemap.grid_size)?;
+		writeln!(f, "player {} {} {}", self.player_position.x, self.player_position.y, self.player_angle)?;
+		for ([x, y], object) in &self.statics {
+			let blocking = if object.blocking { "block" } else { "pass" };
+			write!(f, "static {} {} {} {}", x, y, object.texid, blocking)?;
+			match object.pickup {
+				None => writeln!(f)?,
+				Some(pickup) => writeln!(f, " {}", pickup_code(pickup))?
+			}
+		}
+		for enemy in &self.enemies {
+			let state = if enemy.patrol { "patrol" } else { "stand" };
+			writeln!(f, "enemy {} {} {} {}", enemy.position.x, enemy.position.y, enemy.angle, state)?;
+		}
+		for (texid, animation) in tilemap.animations.iter() {
+			writeln!(f, "anim {}", animation_code(texid, animation))?;
+		}
+		writeln!(f, "tiles")?;
+		for row in tilemap.data.chunks(tilemap.width.max(1) as usize) {
+			let codes: Vec<String> = row.iter().map(tile_code).collect();
+			writeln!(f, "{}", codes.join(" "))?;
+		}
+		Ok(())
+	}
+}
+
+
+#[test]
+fn mapfile_round_trip() {
+	let mut tilemap = TileMap::test_tilemap();
+	tilemap.set_tile(glam::uvec2(2, 3), TileType::Gate(5, 1));
+	tilemap.set_tile(glam::uvec2(0, 0), TileType::Wall(WallFaces { north: 1, south: 2, east: 3, west: 4 }));
+	tilemap.animations.insert(3, TextureAnimation { first: 20, count: 4, frame_duration: 0.25 });
+	let map = MapData {
+		tilemap,
+		player_position: glam::vec2(200.0, 200.0),
+		player_angle: 0.3,
+		statics: BTreeMap::from([
+			([1, 1], Static::decoration(3, true)),
+			([2, 1], Static::pickup(4, Pickup::Ammo(8)))
+		]),
+		enemies: vec![
+			EnemySpawn { position: glam::vec2(150.0, 550.0), angle: 1.5, patrol: false },
+			EnemySpawn { position: glam::vec2(450.0, 650.0), angle: 0.0, patrol: true }
+		]
+	};
+	let parsed: MapData = map.to_string().parse().unwrap();
+	assert_eq!(parsed, map);
+}
+
+#[test]
+fn mapfile_wall_faces() {
+	assert_eq!(parse_tile("W3"), Some(TileType::wall(3)));
+	assert_eq!(parse_tile("W1/2/3/4"), Some(TileType::Wall(WallFaces { north: 1, south: 2, east: 3, west: 4 })));
+	assert_eq!(tile_code(&TileType::Wall(WallFaces::from_array([1, 2, 3, 4]))), "W1/2/3/4");
+	assert_eq!(tile_code(&TileType::wall(3)), "W3");
+	assert_eq!(parse_tile("W1/2/3"), None);
+	assert_eq!(parse_tile("W1/2/x/4"), None);
+}
+
+#[test]
+fn mapfile_wrong_row_length() {
+	let text = "size 3 2\ngrid 100\nplayer 150 150 0\ntiles\nW0 W0 W0\nW0 W0\n";
+	assert!(matches!(
+		text.parse::<MapData>(),
+		Err(MapFileError::WrongRowLength { line: 6, expected: 3, found: 2 })
+	));
+}
+
+#[test]
+fn mapfile_wrong_row_count() {
+	let text = "size 2 2\ngrid 100\nplayer 150 150 0\ntiles\nW0 W0\n";
+	assert!(matches!(
+		text.parse::<MapData>(),
+		Err(MapFileError::WrongRowCount { expected: 2, found: 1 })
+	));
+}
+
+#[test]
+fn mapfile_unknown_tile_code() {
+	let text = "size 2 1\ngrid 100\nplayer 150 150 0\ntiles\nW0 X3\n";
+	assert!(matches!(
+		text.parse::<MapData>(),
+		Err(MapFileError::UnknownTileCode { line: 5, column: 1, .. })
+	));
+	let text = "size 1 1\ngrid 100\nplayer 150 150 0\ntiles\nE3\n";
+	assert!(matches!(text.parse::<MapData>(), Err(MapFileError::UnknownTileCode { .. })));
+}
+
+#[test]
+fn mapfile_invalid_static() {
+	let text = "size 1 1\ngrid 100\nplayer 150 150 0\nstatic 0 0 1 solid\ntiles\nE0/0\n";
+	assert!(matches!(text.parse::<MapData>(), Err(MapFileError::InvalidValue { line: 4 })));
+	let text = "size 1 1\ngrid 100\nplayer 150 150 0\nstatic 0 0 1 pass gold:3\ntiles\nE0/0\n";
+	assert!(matches!(text.parse::<MapData>(), Err(MapFileError::InvalidValue { line: 4 })));
+}
+
+#[test]
+fn mapfile_missing_field() {
+	let text = "size 1 1\nplayer 150 150 0\ntiles\nW0\n";
+	assert!(matches!(text.parse::<MapData>(), Err(MapFileError::MissingField("grid"))));
+}
+
+#[test]
+fn mapfile_invalid_size_and_grid() {
+	let text = "size 4294967295 2
+grid 100
+player 150 150 0
+tiles
+W0
+W0
+";
+	assert!(matches!(text.parse::<MapData>(), Err(MapFileError::InvalidValue { line: 1 })));
+	let text = "size 4294967295 1
+grid 100
+player 150 150 0
+tiles
+W0
+";
+	assert!(matches!(text.parse::<MapData>(), Err(MapFileError::WrongRowLength { line: 5, .. })));
+	for grid in ["0", "-100", "inf", "NaN"] {
+		let text = format!("size 1 1\ngrid {}\nplayer 150 150 0\ntiles\nW0\n", grid);
+		assert!(matches!(text.parse::<MapData>(), Err(MapFileError::InvalidValue { line: 2 })));
+	}
+}
+
+#[test]
+fn mapfile_test_asset_matches_test_tilemap() {
+	let map = MapData::load("asset/test.map").unwrap();
+	assert_eq!(map.tilemap, TileMap::test_tilemap());
+	assert_eq!(map.statics.len(), 2);
+	assert_eq!(map.enemies.len(), 2);
+}
@@ -0,0 +1,281 @@
+// A* pathfinding over the tiles for AI navigation. Paths are lists of tile coords from the start to the goal.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+
+use super::{TileMap, TileType};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Connectivity {
+	Four,
+	Eight // diagonal moves only when both side tiles are walkable(no corner cutting)
+}
+
+impl TileMap {
+	// Empty and door tiles which are not covered by a moving push wall.
+	pub fn is_walkable(&self, coord: glam::UVec2) -> bool {
+		matches!(self.get_tile(coord), Some(TileType::Empty(_, _) | TileType::Door(_, _)))
+			&& !self.pushwalls.iter().any(|pushwall| {
+				let (first, second) = pushwall.covering_tiles();
+				first == coord || second == Some(coord)
+			})
+	}
+}
+
+type Path = Vec<glam::UVec2>;
+
+#[derive(Clone, Debug)]
+pub struct PathFinder {
+	connectivity: Connectivity,
+	blocked: BTreeSet<[u32; 2]>, // extra tiles not to walk through(e.g. closed doors, blocking objects)
+	cache: BTreeMap<[u32; 2], BTreeMap<[u32; 2], Option<Path>>>, // goal -> start -> path
+	revision: u64 // TileMap::get_revision() the cache is made from
+}
+
+// The cache doesn't change the paths found, so it's not compared.
+impl PartialEq for PathFinder {
+	fn eq(&self, other: &Self) -> bool {
+		self.connectivity == other.connectivity && self.blocked == other.blocked
+	}
+}
+
+impl PathFinder {
+	// Move costs in integer to keep the search order deterministic.
+	const STRAIGHT_COST: u32 = 10;
+	const DIAGONAL_COST: u32 = 14;
+	const MAX_CACHED_GOALS: usize = 64; // The whole cache is dropped over this, for moving goals like the player.
+
+	pub fn new(connectivity: Connectivity) -> Self {
+		Self { connectivity, blocked: BTreeSet::new(), cache: BTreeMap::new(), revision: 0 }
+	}
+
+	// Replace the extra blocked tiles. Clears the cache when they changed.
+	pub fn set_blocked(&mut self, blocked: impl IntoIterator<Item = glam::UVec2>) {
+		let blocked = blocked.into_iter().map(|coord| coord.to_array()).collect();
+		if blocked != self.blocked {
+			self.blocked = blocked;
+			self.cache.clear();
+		}
+	}
+
+	// The cache is also dropped by find_path() when the tiles changed(e.g. a door opened, a push wall moved).
+	pub fn clear_cache(&mut self) {
+		self.cache.clear();
+	}
+
+	pub fn is_cached(&self, start: glam::UVec2, goal: glam::UVec2) -> bool {
+		self.cache.get(&goal.to_array()).is_some_and(|paths| paths.contains_key(&start.to_array()))
+	}
+
+	fn is_walkable(&self, tilemap: &TileMap, coord: glam::UVec2) -> bool {
+		tilemap.is_walkable(coord) && !self.blocked.contains(&coord.to_array())
+	}
+
+	fn neighbors(&self, tilemap: &TileMap, coord: glam::UVec2) -> Vec<(glam::UVec2, u32)> {
+		// Negative coords wrap around and are out of the map.
+		let offset = |dx: i32, dy: i32| Some((coord.as_ivec2() + glam::ivec2(dx, dy)).as_uvec2()).filter(|next| self.is_walkable(tilemap, *next));
+		let mut neighbors: Vec<_> = [(1, 0), (-1, 0), (0, 1), (0, -1)].into_iter()
+			.filter_map(|(dx, dy)| offset(dx, dy).map(|next| (next, Self::STRAIGHT_COST)))
+			.collect();
+		if self.connectivity == Connectivity::Eight {
+			for (dx, dy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+				if offset(dx, 0).is_some() && offset(0, dy).is_some() {
+					neighbors.extend(offset(dx, dy).map(|next| (next, Self::DIAGONAL_COST)));
+				}
+			}
+		}
+		neighbors
+	}
+
+	fn heuristic(&self, from: glam::UVec2, to: glam::UVec2) -> u32 {
+		let d = (from.as_ivec2() - to.as_ivec2()).abs().as_uvec2();
+		match self.connectivity {
+			Connectivity::Four => (d.x + d.y) * Self::STRAIGHT_COST,
+			Connectivity::Eight => d.max_element().abs_diff(d.min_element()) * Self::STRAIGHT_COST + d.min_element() * Self::DIAGONAL_COST
+		}
+	}
+
+	// Shortest path including both ends. None if the goal is unreachable or not walkable.
+	pub fn find_path(&mut self, tilemap: &TileMap, start: glam::UVec2, goal: glam::UVec2) -> Option<Path> {
+		if self.revision != tilemap.get_revision() {
+			self.revision = tilemap.get_revision();
+			self.cache.clear();
+		}
+		if let Some(path) = self.cache.get(&goal.to_array()).and_then(|paths| paths.get(&start.to_array())) {
+			return path.clone();
+		}
+		let path = self.search(tilemap, start, goal);
+		if self.cache.len() >= Self::MAX_CACHED_GOALS && !self.cache.contains_key(&goal.to_array()) {
+			self.cache.clear();
+		}
+		self.cache.entry(goal.to_array()).or_default().insert(start.to_array(), path.clone());
+		path
+	}
+
+	fn search(&self, tilemap: &TileMap, start: glam::UVec2, goal: glam::UVec2) -> Option<Path> {
+		if !self.is_walkable(tilemap, start) || !self.is_walkable(tilemap, goal) {
+			return None;
+		}
+		let mut open = BinaryHeap::from([Reverse((self.heuristic(start, goal), 0, start.to_array()))]);
+		let mut cost = BTreeMap::from([(start.to_array(), 0)]);
+		let mut came_from = BTreeMap::<[u32; 2], [u32; 2]>::new();
+
+		while let Some(Reverse((_, g, current))) = open.pop() {
+			if current == goal.to_array() {
+				let mut path = vec![goal];
+				let mut coord = current;
+				while let Some(previous) = came_from.get(&coord) {
+					path.push(glam::UVec2::from_array(*previous));
+					coord = *previous;
+				}
+				path.reverse();
+				return Some(path);
+			}
+			if cost.get(&current).is_some_and(|best| g > *best) {
+				continue; // stale entry
+			}
+			for (next, step) in self.neighbors(tilemap, glam::UVec2::from_array(current)) {
+				let next_cost = g + step;
+				if cost.get(&next.to_array()).is_none_or(|best| next_cost < *best) {
+					cost.insert(next.to_array(), next_cost);
+					came_from.insert(next.to_array(), current);
+					open.push(Reverse((next_cost + self.heuristic(next, goal), next_cost, next.to_array())));
+				}
+			}
+		}
+		None
+	}
+
+	// Whether a straight walk between the tile centers crosses only walkable tiles.
+	// Touching a corner needs both tiles around it, so it doesn't cut corners either.
+	pub fn line_of_sight(&self, tilemap: &TileMap, from: glam::UVec2, to: glam::UVec2) -> bool {
+		let dir = to.as_vec2() - from.as_vec2();
+		let step = dir.signum().as_ivec2();
+		let delta_dist = dir.abs().recip(); // ray parameter per tile. Infinite on an axis without movement.
+		let mut side_dist = glam::vec2(0.5, 0.5) * delta_dist;
+		let mut coord = from.as_ivec2();
+		let target = to.as_ivec2();
+
+		while coord != target {
+			if !self.is_walkable(tilemap, coord.as_uvec2()) {
+				return false;
+			}
+			if side_dist.x < side_dist.y {
+				side_dist.x += delta_dist.x;
+				coord.x += step.x;
+			} else if side_dist.y < side_dist.x {
+				side_dist.y += delta_dist.y;
+				coord.y += step.y;
+			} else {
+				// Through the corner. Both tiles beside must be walkable.
+				let side_x = (coord + glam::ivec2(step.x, 0)).as_uvec2();
+				let side_y = (coord + glam::ivec2(0, step.y)).as_uvec2();
+				if !self.is_walkable(tilemap, side_x) || !self.is_walkable(tilemap, side_y) {
+					return false;
+				}
+				side_dist += delta_dist;
+				coord += step;
+			}
+		}
+		self.is_walkable(tilemap, coord.as_uvec2())
+	}
+
+	// Remove the waypoints which can be skipped by walking straight.
+	pub fn smooth_path(&self, tilemap: &TileMap, path: &[glam::UVec2]) -> Vec<glam::UVec2> {
+		let Some(&first) = path.first() else {
+			return Vec::new();
+		};
+		let mut smoothed = vec![first];
+		let mut anchor = 0;
+		for i in 1..path.len() {
+			if !self.line_of_sight(tilemap, path[anchor], path[i]) {
+				anchor = i - 1;
+				smoothed.push(path[anchor]);
+			}
+		}
+		if path.len() > 1 {
+			smoothed.push(path[path.len() - 1]);
+		}
+		smoothed
+	}
+}
+
+
+#[test]
+fn pathfind_test_tilemap() {
+	let tilemap = TileMap::test_tilemap();
+	let mut finder = PathFinder::new(Connectivity::Four);
+
+	// Through the door at (6, 2).
+	let path = finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)).unwrap();
+	assert_eq!(path.len(), 8);
+	assert_eq!(path.first(), Some(&glam::uvec2(1, 1)));
+	assert_eq!(path.last(), Some(&glam::uvec2(6, 3)));
+	assert!(path.windows(2).all(|w| { let d = (w[0].as_ivec2() - w[1].as_ivec2()).abs(); d.x + d.y == 1 }));
+	assert!(finder.is_cached(glam::uvec2(1, 1), glam::uvec2(6, 3)));
+
+	// Around the wall when the door is closed.
+	finder.set_blocked([glam::uvec2(6, 2)]);
+	assert!(!finder.is_cached(glam::uvec2(1, 1), glam::uvec2(6, 3)));
+	let path = finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)).unwrap();
+	assert_eq!(path.len(), 12);
+	assert!(!path.contains(&glam::uvec2(6, 2)));
+
+	// 8-connected doesn't cut the corner of the wall at (3, 2).
+	let mut finder = PathFinder::new(Connectivity::Eight);
+	let path = finder.find_path(&tilemap, glam::uvec2(2, 2), glam::uvec2(3, 3)).unwrap();
+	assert_eq!(path, vec![glam::uvec2(2, 2), glam::uvec2(2, 3), glam::uvec2(3, 3)]);
+	let path = finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(2, 6)).unwrap();
+	assert_eq!(path.len(), 6);
+}
+
+#[test]
+fn pathfind_unreachable() {
+	let tilemap = TileMap::test_tilemap();
+	let mut finder = PathFinder::new(Connectivity::Eight);
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(0, 0)), None); // wall
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(5, 3)), None); // push wall
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(100, 3)), None); // out of the map
+	// (6, 3) is closed in by the push wall and the blocked tiles.
+	finder.set_blocked([glam::uvec2(6, 2), glam::uvec2(6, 4)]);
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)), None);
+	assert!(finder.is_cached(glam::uvec2(1, 1), glam::uvec2(6, 3)));
+}
+
+#[test]
+fn pathfind_smoothing() {
+	let tilemap = TileMap::test_tilemap();
+	let mut finder = PathFinder::new(Connectivity::Four);
+	assert!(finder.line_of_sight(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 1)));
+	assert!(!finder.line_of_sight(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)));
+	assert!(!finder.line_of_sight(&tilemap, glam::uvec2(2, 2), glam::uvec2(3, 3))); // cutting the corner of (3, 2)
+
+	let path = finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 1)).unwrap();
+	assert_eq!(finder.smooth_path(&tilemap, &path), vec![glam::uvec2(1, 1), glam::uvec2(6, 1)]);
+
+	let path = finder.find_path(&tilemap, glam::uvec2(6, 1), glam::uvec2(1, 6)).unwrap();
+	let smoothed = finder.smooth_path(&tilemap, &path);
+	assert!(smoothed.len() < path.len());
+	assert_eq!(smoothed.first(), path.first());
+	assert_eq!(smoothed.last(), path.last());
+	assert!(smoothed.windows(2).all(|w| finder.line_of_sight(&tilemap, w[0], w[1])));
+}
+
+#[test]
+fn pathfind_cache_follows_tile_changes() {
+	let mut tilemap = TileMap::test_tilemap();
+	let mut finder = PathFinder::new(Connectivity::Four);
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)).map(|path| path.len()), Some(8));
+
+	// Walled up door
+	tilemap.set_tile(glam::uvec2(6, 2), TileType::wall(0));
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)).map(|path| path.len()), Some(12));
+
+	// Push wall moved out of the way
+	assert!(tilemap.push_wall(glam::uvec2(5, 3), glam::IVec2::X));
+	tilemap.tick_pushwalls(10.0, |_| false);
+	assert!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(5, 3)).is_some());
+	assert!(finder.is_cached(glam::uvec2(1, 1), glam::uvec2(5, 3)));
+	assert!(!finder.is_cached(glam::uvec2(1, 1), glam::uvec2(6, 3)));
+	assert_eq!(finder.find_path(&tilemap, glam::uvec2(1, 1), glam::uvec2(6, 3)), None);
+}
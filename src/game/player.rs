@@ -0,0 +1,180 @@
+// Player stats kept across deaths and the life cycle of the player. Health is the entity's Health component.
+
+use std::collections::BTreeSet;
+
+use super::{AmmoType, Health, Pickup, Weapons};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PlayerState {
+	Alive,
+	Dead { timer: f32 }, // seconds left of the death camera
+	GameOver
+}
+
+// Player input sampled for one tick.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PlayerInput {
+	pub dir: glam::Vec2, // x: strafe right, y: forward. Normalized or zero.
+	pub mouse_x: f32, // relative mouse movement since the last tick
+	pub use_pressed: bool,
+	pub fire: bool, // trigger is held
+	pub select_weapon: Option<usize>
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerStats {
+	pub armor: i32,
+	pub lives: u32, // extra lives left
+	pub state: PlayerState,
+	pub killer: Option<glam::Vec2>, // world position the death camera turns to
+	pub weapons: Weapons,
+	pub score: u32,
+	pub keys: BTreeSet<u32>
+}
+
+impl Default for PlayerStats {
+	fn default() -> Self {
+		Self {
+			armor: 0,
+			lives: Self::START_LIVES,
+			state: PlayerState::Alive,
+			killer: None,
+			weapons: Weapons::default(),
+			score: 0,
+			keys: BTreeSet::new()
+		}
+	}
+}
+
+impl PlayerStats {
+	pub const MAX_HEALTH: i32 = 100;
+	pub const MAX_ARMOR: i32 = 100;
+	pub const START_LIVES: u32 = 3;
+	pub const DEATH_TIME: f32 = 2.0;
+	pub const DEATH_CAMERA_TURN_SPEED: f32 = std::f32::consts::PI; // radian per second
+	pub const MOVE_SPEED: f32 = 300.0; // world units per second
+	pub const MOUSE_SENSITIVITY: f32 = 100.0;
+
+	pub fn is_alive(&self) -> bool {
+		self.state == PlayerState::Alive
+	}
+
+	// Armor takes a half of the damage while it lasts. Returns the damage left for the health.
+	pub fn absorb(&mut self, amount: i32) -> i32 {
+		let absorbed = (amount / 2).min(self.armor);
+		self.armor -= absorbed;
+		amount - absorbed
+	}
+
+	// Called when the health reaches 0.
+	pub fn die(&mut self, killer: Option<glam::Vec2>) {
+		if !self.is_alive() {
+			return;
+		}
+		self.state = PlayerState::Dead { timer: Self::DEATH_TIME };
+		self.killer = killer;
+	}
+
+	// Apply the pickup to the stats and the health of the player.
+	// Returns false if it's not needed(full health or ammo), then it stays in the world.
+	pub fn take_pickup(&mut self, pickup: Pickup, health: &mut Health) -> bool {
+		if !self.is_alive() {
+			return false;
+		}
+		match pickup {
+			Pickup::Health(amount) => {
+				if health.current >= health.max {
+					return false;
+				}
+				health.current = health.current.saturating_add(i32::try_from(amount).unwrap_or(i32::MAX)).min(health.max);
+			},
+			Pickup::Ammo(amount) => {
+				if self.weapons.get_ammo(AmmoType::Bullet) >= Weapons::MAX_AMMO {
+					return false;
+				}
+				self.weapons.add_ammo(AmmoType::Bullet, amount);
+			},
+			Pickup::Treasure(score) => self.score = self.score.saturating_add(score),
+			Pickup::Key(id) => {
+				self.keys.insert(id);
+			}
+		}
+		true
+	}
+
+	// Returns true if the player should respawn now. Without lives left, the game is over instead.
+	pub fn tick(&mut self, delta: f32) -> bool {
+		let PlayerState::Dead { timer } = self.state else {
+			return false;
+		};
+		if timer > delta {
+			self.state = PlayerState::Dead { timer: timer - delta };
+			return false;
+		}
+		if self.lives == 0 {
+			self.state = PlayerState::GameOver;
+			return false;
+		}
+		self.lives -= 1;
+		self.armor = 0;
+		self.weapons = Weapons::default();
+		self.state = PlayerState::Alive;
+		self.killer = None;
+		true
+	}
+}
+
+
+#[test]
+fn pickup_rules() {
+	let mut stats = PlayerStats::default();
+	let mut health = Health { current: 90, max: PlayerStats::MAX_HEALTH };
+	assert!(stats.take_pickup(Pickup::Health(25), &mut health));
+	assert_eq!(health.current, PlayerStats::MAX_HEALTH); // Capped
+	assert!(!stats.take_pickup(Pickup::Health(25), &mut health));
+
+	assert!(stats.take_pickup(Pickup::Ammo(8), &mut health));
+	assert_eq!(stats.weapons.get_ammo(AmmoType::Bullet), 16);
+	stats.weapons.add_ammo(AmmoType::Bullet, Weapons::MAX_AMMO);
+	assert!(!stats.take_pickup(Pickup::Ammo(8), &mut health));
+
+	assert!(stats.take_pickup(Pickup::Treasure(100), &mut health));
+	assert!(stats.take_pickup(Pickup::Treasure(500), &mut health));
+	assert_eq!(stats.score, 600);
+
+	assert!(stats.take_pickup(Pickup::Key(1), &mut health));
+	assert!(stats.keys.contains(&1) && !stats.keys.contains(&0));
+
+	// Huge amounts from map files are capped.
+	health.current = 1;
+	assert!(stats.take_pickup(Pickup::Health(u32::MAX), &mut health));
+	assert_eq!(health.current, PlayerStats::MAX_HEALTH);
+	assert!(stats.take_pickup(Pickup::Treasure(u32::MAX), &mut health));
+	assert_eq!(stats.score, u32::MAX);
+
+	stats.die(None);
+	assert!(!stats.take_pickup(Pickup::Treasure(100), &mut health));
+}
+
+#[test]
+fn player_stats_transitions() {
+	let mut stats = PlayerStats { armor: 10, ..Default::default() };
+	assert_eq!(stats.absorb(30), 20);
+	assert_eq!(stats.armor, 0);
+	assert_eq!(stats.absorb(30), 30);
+
+	stats.die(Some(glam::vec2(100.0, 0.0)));
+	assert_eq!(stats.state, PlayerState::Dead { timer: PlayerStats::DEATH_TIME });
+	assert!(!stats.tick(PlayerStats::DEATH_TIME * 0.5));
+	assert!(stats.tick(PlayerStats::DEATH_TIME));
+	assert!(stats.is_alive());
+	assert_eq!(stats.lives, PlayerStats::START_LIVES - 1);
+	assert_eq!(stats.killer, None);
+
+	stats.lives = 0;
+	stats.die(None);
+	assert!(!stats.tick(PlayerStats::DEATH_TIME));
+	assert_eq!(stats.state, PlayerState::GameOver);
+	stats.die(None);
+	assert_eq!(stats.state, PlayerState::GameOver);
+}
@@ -0,0 +1,59 @@
+// Secret push wall which is sliding after the player uses TileType::PushWall tile.
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PushWall {
+	pub origin: glam::UVec2,
+	pub direction: glam::IVec2, // one of ±X, ±Y
+	pub texid: u32,
+	pub distance: u32, // tiles to move
+	pub progress: f32 // tiles moved
+}
+
+impl PushWall {
+	pub const SPEED: f32 = 0.5; // tiles per second
+	pub const MAX_DISTANCE: u32 = 2;
+
+	// Position of the block in tile space. (left top corner)
+	pub fn position(&self) -> glam::Vec2 {
+		self.origin.as_vec2() + self.direction.as_vec2() * self.progress
+	}
+
+	// Tiles which the block is covering now. Second one is None when the block fits a tile.
+	pub fn covering_tiles(&self) -> (glam::UVec2, Option<glam::UVec2>) {
+		let moved = self.progress.floor();
+		let first = (self.origin.as_ivec2() + self.direction * moved as i32).as_uvec2();
+		if self.progress - moved > 0.0 {
+			(first, Some((first.as_ivec2() + self.direction).as_uvec2()))
+		} else {
+			(first, None)
+		}
+	}
+
+	pub fn destination(&self) -> glam::UVec2 {
+		(self.origin.as_ivec2() + self.direction * self.distance as i32).as_uvec2()
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.progress >= self.distance as f32
+	}
+
+	pub fn advance(&mut self, delta: f32) {
+		self.progress = (self.progress + Self::SPEED * delta).min(self.distance as f32);
+	}
+}
+
+
+#[test]
+fn pushwall_covering_tiles() {
+	let mut pushwall = PushWall {
+		origin: glam::uvec2(3, 3), direction: glam::IVec2::NEG_X, texid: 0, distance: 2, progress: 0.0
+	};
+	assert_eq!(pushwall.covering_tiles(), (glam::uvec2(3, 3), None));
+	pushwall.advance(1.0);
+	assert_eq!(pushwall.position(), glam::vec2(2.5, 3.0));
+	assert_eq!(pushwall.covering_tiles(), (glam::uvec2(3, 3), Some(glam::uvec2(2, 3))));
+	pushwall.advance(10.0);
+	assert!(pushwall.is_finished());
+	assert_eq!(pushwall.covering_tiles(), (glam::uvec2(1, 3), None));
+	assert_eq!(pushwall.destination(), glam::uvec2(1, 3));
+}
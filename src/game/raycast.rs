@@ -0,0 +1,233 @@
+// CPU version of the DDA raycasting in firstperson_wall_compute.wgsl for the game logic.
+
+use super::{TileMap, TileType, DoorOrientation, PushWall};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HitSide {
+	X, // the face perpendicular to x axis
+	Y
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RaycastHit {
+	pub coord: glam::UVec2,
+	pub side: HitSide,
+	pub point: glam::Vec2, // world space
+	pub distance: f32, // world space
+	pub u_offset: f32
+}
+
+impl TileMap {
+	// Cast a ray from the world position. Returns None if nothing is hit within max_distance.
+	pub fn raycast(&self, origin: glam::Vec2, direction: glam::Vec2, max_distance: f32) -> Option<RaycastHit> {
+		let dir = direction.normalize_or_zero();
+		if dir == glam::Vec2::ZERO {
+			return None;
+		}
+		let pos = origin / self.grid_size;
+		let max = max_distance / self.grid_size;
+
+		let delta_dist = dir.abs().recip();
+		let step = dir.signum().as_ivec2();
+		let side_dist_axis = |p: f32, d: f32, delta: f32| match d {
+			0.0 => f32::INFINITY,
+			d if d < 0.0 => p.fract() * delta,
+			_ => (1.0 - p.fract()) * delta
+		};
+		let mut side_dist = glam::vec2(side_dist_axis(pos.x, dir.x, delta_dist.x), side_dist_axis(pos.y, dir.y, delta_dist.y));
+		let mut tile_coord = pos.floor().as_ivec2();
+
+		// Moving push walls are not in the tile data.
+		let pushwall_hit = self.pushwalls.iter()
+			.filter_map(|p| raycast_pushwall(pos, dir, p))
+			.min_by(|a, b| a.distance.total_cmp(&b.distance));
+
+		let mut entry = 0.0f32;
+		let mut side: Option<HitSide> = None; // None: the start tile. Walls in the start tile are ignored.
+		loop {
+			if entry > max || tile_coord.cmplt(glam::IVec2::ZERO).any() {
+				return None;
+			}
+			let exit = side_dist.x.min(side_dist.y);
+			let coord = tile_coord.as_uvec2();
+			let hit = match (self.get_tile(coord)?, side) {
+				(TileType::Empty(_, _), _) | (TileType::Wall(_) | TileType::PushWall(_) | TileType::Gate(_, _), None) => None,
+				(TileType::Wall(_) | TileType::PushWall(_) | TileType::Gate(_, _), Some(side)) => Some(wall_hit(pos, dir, coord, entry, side)),
+				(TileType::Door(orientation, _), _) => self.doors.get(&[coord.x, coord.y])
+					.and_then(|door| raycast_door(pos, dir, coord, *orientation, door.open_amount, entry, exit))
+			};
+			if let Some(hit) = hit.or(pushwall_hit.clone().filter(|hit| hit.distance <= exit)).filter(|hit| hit.distance <= max) {
+				return Some(self.to_world(hit));
+			}
+
+			// March ray until reaching another tile.
+			if side_dist.x < side_dist.y {
+				entry = side_dist.x;
+				side_dist.x += delta_dist.x;
+				tile_coord.x += step.x;
+				side = Some(HitSide::X);
+			} else {
+				entry = side_dist.y;
+				side_dist.y += delta_dist.y;
+				tile_coord.y += step.y;
+				side = Some(HitSide::Y);
+			}
+		}
+	}
+
+	// True if nothing blocks the segment between two world positions.
+	pub fn has_line_of_sight(&self, a: glam::Vec2, b: glam::Vec2) -> bool {
+		self.raycast(a, b - a, a.distance(b)).is_none()
+	}
+
+	fn to_world(&self, hit: RaycastHit) -> RaycastHit {
+		RaycastHit { point: hit.point * self.grid_size, distance: hit.distance * self.grid_size, ..hit }
+	}
+}
+
+// Hit on the face of a full tile. (tile space)
+fn wall_hit(pos: glam::Vec2, dir: glam::Vec2, coord: glam::UVec2, distance: f32, side: HitSide) -> RaycastHit {
+	let point = pos + dir * distance;
+	let u_offset = match side {
+		HitSide::X => {
+			let frc = point.y.fract();
+			if dir.x > 0.0 { frc } else { 1.0 - frc }
+		},
+		HitSide::Y => {
+			let frc = point.x.fract();
+			if dir.y < 0.0 { frc } else { 1.0 - frc }
+		}
+	};
+	RaycastHit { coord, side, point, distance, u_offset }
+}
+
+// Hit test the door slab placed at the middle of the tile. (tile space)
+fn raycast_door(pos: glam::Vec2, dir: glam::Vec2, coord: glam::UVec2, orientation: DoorOrientation, open_amount: f32, entry: f32, exit: f32) -> Option<RaycastHit> {
+	let (axis, side) = match orientation {
+		DoorOrientation::Vertical => (0, HitSide::X),
+		DoorOrientation::Horizontal => (1, HitSide::Y)
+	};
+	if dir[axis] == 0.0 {
+		return None;
+	}
+	let mid = coord[axis] as f32 + 0.5;
+	let distance = (mid - pos[axis]) / dir[axis];
+	if distance < entry || distance > exit {
+		return None;
+	}
+	let point = pos + dir * distance;
+	let frc = point[1 - axis] - coord[1 - axis] as f32;
+	if frc < open_amount { // Opened part
+		return None;
+	}
+	Some(RaycastHit { coord, side, point, distance, u_offset: frc - open_amount })
+}
+
+// Hit test the moving push wall block with slab method. (tile space)
+fn raycast_pushwall(pos: glam::Vec2, dir: glam::Vec2, pushwall: &PushWall) -> Option<RaycastHit> {
+	let box_min = pushwall.position();
+	let box_max = box_min + glam::Vec2::ONE;
+	let t1 = (box_min - pos) / dir;
+	let t2 = (box_max - pos) / dir;
+	let t_near = t1.min(t2);
+	let t_far = t1.max(t2);
+	let distance = t_near.max_element();
+	if distance > t_far.min_element() || distance < 0.0 {
+		return None;
+	}
+	let point = pos + dir * distance;
+	let side = if t_near.x > t_near.y { HitSide::X } else { HitSide::Y };
+	let u_offset = match side {
+		HitSide::X => {
+			let frc = point.y - box_min.y;
+			if dir.x > 0.0 { frc } else { 1.0 - frc }
+		},
+		HitSide::Y => {
+			let frc = point.x - box_min.x;
+			if dir.y < 0.0 { frc } else { 1.0 - frc }
+		}
+	};
+	let coord = point.floor().as_uvec2(); // NOTE: Not the tile the block came from.
+	Some(RaycastHit { coord, side, point, distance, u_offset })
+}
+
+
+#[test]
+fn raycast_walls() {
+	let tilemap = TileMap::test_tilemap();
+
+	let hit = tilemap.raycast(glam::vec2(150.0, 150.0), glam::Vec2::X, 1000.0).unwrap();
+	assert_eq!(hit.coord, glam::uvec2(7, 1));
+	assert_eq!(hit.side, HitSide::X);
+	assert!((hit.point - glam::vec2(700.0, 150.0)).length() < 1e-3);
+	assert!((hit.distance - 550.0).abs() < 1e-3);
+	assert!((hit.u_offset - 0.5).abs() < 1e-4);
+
+	let hit = tilemap.raycast(glam::vec2(130.0, 150.0), glam::Vec2::Y, 1000.0).unwrap();
+	assert_eq!(hit.coord, glam::uvec2(1, 7));
+	assert_eq!(hit.side, HitSide::Y);
+	assert!((hit.distance - 550.0).abs() < 1e-3);
+	assert!((hit.u_offset - 0.7).abs() < 1e-4);
+
+	// Diagonal ray passes (1, 3), (1, 2) and hits the right face of (0, 2).
+	let hit = tilemap.raycast(glam::vec2(250.0, 350.0), glam::vec2(-1.0, -0.5), 1000.0).unwrap();
+	assert_eq!(hit.coord, glam::uvec2(0, 2));
+	assert_eq!(hit.side, HitSide::X);
+	assert!((hit.point - glam::vec2(100.0, 275.0)).length() < 1e-3);
+
+	assert!(tilemap.raycast(glam::vec2(150.0, 150.0), glam::Vec2::X, 300.0).is_none());
+}
+
+#[test]
+fn raycast_door_and_pushwall() {
+	let mut tilemap = TileMap::test_tilemap();
+
+	// Closed door at (6, 2)
+	let hit = tilemap.raycast(glam::vec2(650.0, 150.0), glam::Vec2::Y, 1000.0).unwrap();
+	assert_eq!(hit.coord, glam::uvec2(6, 2));
+	assert_eq!(hit.side, HitSide::Y);
+	assert!((hit.distance - 100.0).abs() < 1e-3);
+	assert!((hit.u_offset - 0.5).abs() < 1e-4);
+	assert!(!tilemap.has_line_of_sight(glam::vec2(650.0, 150.0), glam::vec2(650.0, 350.0)));
+
+	tilemap.doors.get_mut(&[6, 2]).unwrap().open_amount = 0.6;
+	assert!(tilemap.has_line_of_sight(glam::vec2(650.0, 150.0), glam::vec2(650.0, 350.0)));
+	assert!(!tilemap.has_line_of_sight(glam::vec2(680.0, 150.0), glam::vec2(680.0, 350.0)));
+
+	// Push wall at (5, 3) moving to -x by a half tile.
+	assert!(tilemap.raycast(glam::vec2(350.0, 350.0), glam::Vec2::X, 1000.0).is_some_and(|hit| hit.coord == glam::uvec2(5, 3)));
+	assert!(tilemap.push_wall(glam::uvec2(5, 3), glam::IVec2::NEG_X));
+	tilemap.tick_pushwalls(1.0, |_| false);
+	let hit = tilemap.raycast(glam::vec2(350.0, 350.0), glam::Vec2::X, 1000.0).unwrap();
+	assert_eq!(hit.coord, glam::uvec2(4, 3));
+	assert_eq!(hit.side, HitSide::X);
+	assert!((hit.distance - 100.0).abs() < 1e-3);
+}
+
+#[test]
+fn line_of_sight() {
+	let tilemap = TileMap::test_tilemap();
+	assert!(tilemap.has_line_of_sight(glam::vec2(150.0, 150.0), glam::vec2(650.0, 150.0)));
+	assert!(tilemap.has_line_of_sight(glam::vec2(150.0, 150.0), glam::vec2(250.0, 650.0)));
+	assert!(!tilemap.has_line_of_sight(glam::vec2(150.0, 150.0), glam::vec2(450.0, 550.0))); // through wall (3, 4)
+	assert!(tilemap.has_line_of_sight(glam::vec2(150.0, 150.0), glam::vec2(150.0, 150.0)));
+}
+
+#[test]
+fn raycast_wall_faces() {
+	use super::WallFaces;
+
+	let mut tilemap = TileMap::test_tilemap();
+	let faces = WallFaces { north: 1, south: 2, east: 3, west: 4 };
+	tilemap.set_tile(glam::uvec2(3, 3), TileType::Wall(faces));
+
+	let face = |origin: glam::Vec2, dir: glam::Vec2| {
+		let hit = tilemap.raycast(origin, dir, 1000.0).unwrap();
+		assert_eq!(hit.coord, glam::uvec2(3, 3));
+		faces.get_face(hit.side, dir)
+	};
+	assert_eq!(face(glam::vec2(150.0, 350.0), glam::Vec2::X), faces.west);
+	assert_eq!(face(glam::vec2(450.0, 350.0), -glam::Vec2::X), faces.east);
+	assert_eq!(face(glam::vec2(350.0, 450.0), -glam::Vec2::Y), faces.south);
+	assert_eq!(faces.get_face(HitSide::Y, glam::Vec2::Y), faces.north); // Going south hits the north face
+}
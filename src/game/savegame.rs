@@ -0,0 +1,565 @@
+/*
+
+Save game: the full state of GameWorld in a plain-text file. Pending events and the render interpolation are not saved.
+
+ - '#' starts a comment line. Blank lines are ignored.
+	save <version>			(1: no time and anim lines)
+	rng <state>
+	secrets <found> <total>
+	time <seconds>			(optional)
+	spawn <x> <y> <angle>
+	player <entity id>
+	stats <armor> <lives> <score>
+	state <alive|dead:<timer>|gameover>
+	killer <x> <y>			(optional)
+	key <id>				(zero or more)
+	weapons <current> <cooldown> <switching> <trigger held 0|1>
+	owned <slot>			(zero or more)
+	ammo bullet <amount>
+	entities <next id>
+	entity <id> <player|enemy|static> <x> <y> <angle>
+		(zero or more. Followed by the optional components of the entity)
+		collider <radius> <block|pass>
+		sprite <billboard|directional> <layer>
+		health <current> <max>
+		ai <speed> <stand|patrol|chase|attack|pain|die|dead> <timer> <target_x> <target_y>
+		pickup <health|ammo|treasure|key>:<amount>
+	door <x> <y> <closed|opening|open|closing> <open amount> <timer>
+	pushwall <x> <y> <dir_x> <dir_y> <texid> <distance> <progress>
+	anim <texid> <first frame texid> <frame count> <frame seconds>	(zero or more)
+	size <width> <height>
+	grid <grid_size>
+ - "tiles" line is followed by exactly <height> rows of tile codes(see mapfile.rs). It's the last section.
+
+ */
+
+use std::{fmt, fmt::Write, path::{Path, PathBuf}, str::FromStr};
+use std::collections::BTreeMap;
+
+use super::mapfile::{animation_code, parse_animation, parse_pickup, parse_tile, pickup_code, tile_code};
+use super::{
+	AmmoType, Collider, Connectivity, DirectionalSprite, DoorState, Enemy, EnemyState, Entities, Entity, EntityId, EntityKind,
+	GameWorld, Health, PathFinder, PlayerState, PlayerStats, PushWall, Sprite, TextureAnimations, TileMap, TileType, Transform
+};
+
+#[derive(Debug)]
+pub enum SaveError {
+	OpenFileFailed(std::io::Error),
+	WriteFileFailed(std::io::Error),
+	UnknownKey { line: usize, key: String },
+	InvalidValue { line: usize },
+	UnsupportedVersion(u32),
+	MissingField(&'static str),
+	NoEntity { line: usize }, // component line before any entity line
+	WrongRowCount { expected: u32, found: usize }
+}
+
+impl fmt::Display for SaveError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SaveError::OpenFileFailed(e) => write!(f, "failed to open save file: {}", e),
+			SaveError::WriteFileFailed(e) => write!(f, "failed to write save file: {}", e),
+			SaveError::UnknownKey { line, key } => write!(f, "line {}: unknown key '{}'", line, key),
+			SaveError::InvalidValue { line } => write!(f, "line {}: invalid value", line),
+			SaveError::UnsupportedVersion(version) => write!(f, "unsupported save version {}", version),
+			SaveError::MissingField(field) => write!(f, "missing field '{}'", field),
+			SaveError::NoEntity { line } => write!(f, "line {}: component without entity", line),
+			SaveError::WrongRowCount { expected, found } => write!(f, "expected {} tile rows, found {}", expected, found)
+		}
+	}
+}
+
+impl std::error::Error for SaveError {}
+
+pub const SAVE_VERSION: u32 = 2;
+pub const SAVE_SLOTS: usize = 4;
+
+// File of the save slot in the working directory.
+pub fn save_slot_path(slot: usize) -> PathBuf {
+	PathBuf::from(format!("save{}.sav", slot))
+}
+
+impl GameWorld {
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+		std::fs::write(path, self.to_save_string()).map_err(SaveError::WriteFileFailed)
+	}
+
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, SaveError> {
+		match std::fs::read_to_string(path) {
+			Ok(s) => Self::from_save_string(&s),
+			Err(e) => Err(SaveError::OpenFileFailed(e))
+		}
+	}
+
+	pub fn to_save_string(&self) -> String {
+		let mut s = String::new();
+		self.write_save(&mut s).expect("writing to String never fails");
+		s
+	}
+
+	fn write_save(&self, f: &mut String) -> fmt::Result {
+		writeln!(f, "# Rulf 3D save file")?;
+		writeln!(f, "save {}", SAVE_VERSION)?;
+		writeln!(f, "rng {}", self.rng)?;
+		writeln!(f, "secrets {} {}", self.secrets_found, self.secrets_total)?;
+		writeln!(f, "time {}", self.time)?;
+		writeln!(f, "spawn {} {} {}", self.player_spawn.position.x, self.player_spawn.position.y, self.player_spawn.angle)?;
+		writeln!(f, "player {}", self.player.0)?;
+
+		let stats = &self.player_stats;
+		writeln!(f, "stats {} {} {}", stats.armor, stats.lives, stats.score)?;
+		match stats.state {
+			PlayerState::Alive => writeln!(f, "state alive")?,
+			PlayerState::Dead { timer } => writeln!(f, "state dead:{}", timer)?,
+			PlayerState::GameOver => writeln!(f, "state gameover")?
+		}
+		if let Some(killer) = stats.killer {
+			writeln!(f, "killer {} {}", killer.x, killer.y)?;
+		}
+		for key in &stats.keys {
+			writeln!(f, "key {}", key)?;
+		}
+		let weapons = &stats.weapons;
+		writeln!(f, "weapons {} {} {} {}", weapons.current, weapons.cooldown, weapons.switching, weapons.trigger_held as u8)?;
+		for (slot, _) in weapons.owned.iter().enumerate().filter(|(_, owned)| **owned) {
+			writeln!(f, "owned {}", slot)?;
+		}
+		for (ammo_type, amount) in &weapons.ammo {
+			match ammo_type {
+				AmmoType::Bullet => writeln!(f, "ammo bullet {}", amount)?
+			}
+		}
+
+		writeln!(f, "entities {}", self.entities.get_next_id())?;
+		for (id, entity) in self.entities.iter() {
+			let kind = match entity.kind {
+				EntityKind::Player => "player",
+				EntityKind::Enemy => "enemy",
+				EntityKind::Static => "static"
+			};
+			let transform = &entity.transform;
+			writeln!(f, "entity {} {} {} {} {}", id.0, kind, transform.position.x, transform.position.y, transform.angle)?;
+			if let Some(collider) = entity.collider {
+				writeln!(f, "collider {} {}", collider.radius, if collider.blocking { "block" } else { "pass" })?;
+			}
+			match entity.sprite {
+				None => (),
+				Some(Sprite::Billboard(layer)) => writeln!(f, "sprite billboard {}", layer)?,
+				Some(Sprite::Directional(sprite)) => writeln!(f, "sprite directional {}", sprite.base_layer)?
+			}
+			if let Some(health) = entity.health {
+				writeln!(f, "health {} {}", health.current, health.max)?;
+			}
+			if let Some(ai) = &entity.ai {
+				writeln!(f, "ai {} {} {} {} {}", ai.speed, enemy_state_code(ai.state), ai.timer, ai.target.x, ai.target.y)?;
+			}
+			if let Some(pickup) = entity.pickup {
+				writeln!(f, "pickup {}", pickup_code(pickup))?;
+			}
+		}
+
+		let tilemap = &self.tilemap;
+		for ([x, y], door) in &tilemap.doors {
+			let state = match door.state {
+				DoorState::Closed => "closed",
+				DoorState::Opening => "opening",
+				DoorState::Open => "open",
+				DoorState::Closing => "closing"
+			};
+			writeln!(f, "door {} {} {} {} {}", x, y, state, door.open_amount, door.timer)?;
+		}
+		for pushwall in &tilemap.pushwalls {
+			writeln!(f, "pushwall {} {} {} {} {} {} {}", pushwall.origin.x, pushwall.origin.y, pushwall.direction.x, pushwall.direction.y,
+				pushwall.texid, pushwall.distance, pushwall.progress)?;
+		}
+		for (texid, animation) in tilemap.animations.iter() {
+			writeln!(f, "anim {}", animation_code(texid, animation))?;
+		}
+		writeln!(f, "size {} {}", tilemap.width, tilemap.height)?;
+		writeln!(f, "grid {}", tilemap.grid_size)?;
+		writeln!(f, "tiles")?;
+		for row in tilemap.data.chunks(tilemap.width.max(1) as usize) {
+			let codes: Vec<String> = row.iter().map(tile_code).collect();
+			writeln!(f, "{}", codes.join(" "))?;
+		}
+		Ok(())
+	}
+
+	pub fn from_save_string(s: &str) -> Result<Self, SaveError> {
+		let mut version: Option<u32> = None;
+		let mut rng: Option<u32> = None;
+		let mut secrets: Option<[u32; 2]> = None;
+		let mut time = 0.0;
+		let mut spawn: Option<[f32; 3]> = None;
+		let mut player: Option<u32> = None;
+		let mut player_stats = PlayerStats::default();
+		let mut next_id: Option<u32> = None;
+		let mut entities = BTreeMap::<EntityId, Entity>::new();
+		let mut last_entity: Option<EntityId> = None;
+		let mut doors = BTreeMap::<[u32; 2], (DoorState, f32, f32)>::new();
+		let mut pushwalls = Vec::<(usize, PushWall)>::new();
+		let mut animations = TextureAnimations::default();
+		let mut size: Option<[u32; 2]> = None;
+		let mut grid_size: Option<f32> = None;
+		let mut rows: Option<Vec<(usize, &str)>> = None;
+
+		player_stats.weapons.owned = Default::default();
+		player_stats.weapons.ammo.clear();
+
+		for (i, raw) in s.lines().enumerate() {
+			let line = i + 1;
+			let text = raw.trim();
+			if text.is_empty() || text.starts_with('#') {
+				continue;
+			}
+			if let Some(rows) = rows.as_mut() {
+				rows.push((line, text));
+				continue;
+			}
+
+			let mut tokens = text.split_whitespace();
+			let key = tokens.next().unwrap_or_default();
+			let values: Vec<&str> = tokens.collect();
+			let invalid = SaveError::InvalidValue { line };
+			match key {
+				"save" => {
+					let [value] = parse_values::<u32, 1>(line, &values)?;
+					if !(1..=SAVE_VERSION).contains(&value) {
+						return Err(SaveError::UnsupportedVersion(value));
+					}
+					version = Some(value);
+				},
+				"rng" => rng = Some(parse_values::<u32, 1>(line, &values)?[0]),
+				"secrets" => secrets = Some(parse_values(line, &values)?),
+				"time" => time = parse_values::<f32, 1>(line, &values)?[0],
+				"spawn" => spawn = Some(parse_values(line, &values)?),
+				"player" => player = Some(parse_values::<u32, 1>(line, &values)?[0]),
+				"stats" => {
+					let [armor, lives, score] = parse_values::<i64, 3>(line, &values)?;
+					player_stats.armor = armor.try_into().map_err(|_| SaveError::InvalidValue { line })?;
+					player_stats.lives = lives.try_into().map_err(|_| SaveError::InvalidValue { line })?;
+					player_stats.score = score.try_into().map_err(|_| SaveError::InvalidValue { line })?;
+				},
+				"state" => player_stats.state = match values.as_slice() {
+					["alive"] => PlayerState::Alive,
+					["gameover"] => PlayerState::GameOver,
+					[dead] => {
+						let timer = dead.strip_prefix("dead:").and_then(|timer| timer.parse().ok()).ok_or(invalid)?;
+						PlayerState::Dead { timer }
+					},
+					_ => return Err(invalid)
+				},
+				"killer" => player_stats.killer = Some(glam::Vec2::from_array(parse_values(line, &values)?)),
+				"key" => {
+					player_stats.keys.insert(parse_values::<u32, 1>(line, &values)?[0]);
+				},
+				"weapons" => {
+					let [current, cooldown, switching, trigger_held] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let weapons = &mut player_stats.weapons;
+					weapons.current = current.parse().ok().filter(|slot| *slot < weapons.owned.len()).ok_or(SaveError::InvalidValue { line })?;
+					[weapons.cooldown, weapons.switching] = parse_values(line, &[cooldown, switching])?;
+					weapons.trigger_held = parse_values::<u8, 1>(line, &[trigger_held])?[0] != 0;
+				},
+				"owned" => {
+					let [slot] = parse_values::<usize, 1>(line, &values)?;
+					*player_stats.weapons.owned.get_mut(slot).ok_or(invalid)? = true;
+				},
+				"ammo" => match values.as_slice() {
+					["bullet", amount] => {
+						let [amount] = parse_values(line, &[amount])?;
+						player_stats.weapons.ammo.insert(AmmoType::Bullet, amount);
+					},
+					_ => return Err(invalid)
+				},
+				"entities" => next_id = Some(parse_values::<u32, 1>(line, &values)?[0]),
+				"entity" => {
+					let [id, kind, x, y, angle] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let [id] = parse_values::<u32, 1>(line, &[id])?;
+					let kind = match *kind {
+						"player" => EntityKind::Player,
+						"enemy" => EntityKind::Enemy,
+						"static" => EntityKind::Static,
+						_ => return Err(invalid)
+					};
+					let [x, y, angle] = parse_values::<f32, 3>(line, &[x, y, angle])?;
+					entities.insert(EntityId(id), Entity {
+						kind,
+						transform: Transform { position: glam::vec2(x, y), angle },
+						collider: None,
+						sprite: None,
+						health: None,
+						ai: None,
+						pickup: None
+					});
+					last_entity = Some(EntityId(id));
+				},
+				"collider" => {
+					let [radius, blocking] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let [radius] = parse_values(line, &[radius])?;
+					let blocking = match *blocking {
+						"block" => true,
+						"pass" => false,
+						_ => return Err(invalid)
+					};
+					entity_mut(&mut entities, last_entity, line)?.collider = Some(Collider { radius, blocking });
+				},
+				"sprite" => {
+					let [kind, layer] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let [layer] = parse_values(line, &[layer])?;
+					entity_mut(&mut entities, last_entity, line)?.sprite = Some(match *kind {
+						"billboard" => Sprite::Billboard(layer),
+						"directional" => Sprite::Directional(DirectionalSprite { base_layer: layer }),
+						_ => return Err(invalid)
+					});
+				},
+				"health" => {
+					let [current, max] = parse_values(line, &values)?;
+					entity_mut(&mut entities, last_entity, line)?.health = Some(Health { current, max });
+				},
+				"ai" => {
+					let [speed, state, timer, x, y] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let state = parse_enemy_state(state).ok_or(invalid)?;
+					let [speed, timer, x, y] = parse_values::<f32, 4>(line, &[speed, timer, x, y])?;
+					entity_mut(&mut entities, last_entity, line)?.ai = Some(Enemy { speed, state, timer, target: glam::vec2(x, y) });
+				},
+				"pickup" => {
+					let [code] = values.as_slice() else {
+						return Err(invalid);
+					};
+					entity_mut(&mut entities, last_entity, line)?.pickup = Some(parse_pickup(code).ok_or(invalid)?);
+				},
+				"door" => {
+					let [x, y, state, open_amount, timer] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let coord = parse_values::<u32, 2>(line, &[x, y])?;
+					let state = match *state {
+						"closed" => DoorState::Closed,
+						"opening" => DoorState::Opening,
+						"open" => DoorState::Open,
+						"closing" => DoorState::Closing,
+						_ => return Err(invalid)
+					};
+					let [open_amount, timer] = parse_values(line, &[open_amount, timer])?;
+					doors.insert(coord, (state, open_amount, timer));
+				},
+				"pushwall" => {
+					let [x, y, dir_x, dir_y, texid, distance, progress] = values.as_slice() else {
+						return Err(invalid);
+					};
+					let [x, y, texid, distance] = parse_values::<u32, 4>(line, &[x, y, texid, distance])?;
+					let [dir_x, dir_y] = parse_values::<i32, 2>(line, &[dir_x, dir_y])?;
+					let [progress] = parse_values(line, &[progress])?;
+					let direction = glam::ivec2(dir_x, dir_y);
+					let directions = [glam::IVec2::X, glam::IVec2::NEG_X, glam::IVec2::Y, glam::IVec2::NEG_Y];
+					if !directions.contains(&direction) || distance > PushWall::MAX_DISTANCE || !(0.0..=distance as f32).contains(&progress) {
+						return Err(invalid);
+					}
+					pushwalls.push((line, PushWall { origin: glam::uvec2(x, y), direction, texid, distance, progress }));
+				},
+				"anim" => {
+					let (texid, animation) = parse_animation(&values).ok_or(invalid)?;
+					animations.insert(texid, animation);
+				},
+				"size" => {
+					let [width, height] = parse_values::<u32, 2>(line, &values)?;
+					width.checked_mul(height).ok_or(invalid)?;
+					size = Some([width, height]);
+				},
+				"grid" => {
+					let [grid] = parse_values::<f32, 1>(line, &values)?;
+					if !grid.is_finite() || grid <= 0.0 {
+						return Err(invalid);
+					}
+					grid_size = Some(grid);
+				},
+				"tiles" if values.is_empty() => rows = Some(Vec::new()),
+				_ => return Err(SaveError::UnknownKey { line, key: key.to_string() })
+			}
+		}
+
+		version.ok_or(SaveError::MissingField("save"))?;
+		let [width, height] = size.ok_or(SaveError::MissingField("size"))?;
+		let grid_size = grid_size.ok_or(SaveError::MissingField("grid"))?;
+		let rows = rows.ok_or(SaveError::MissingField("tiles"))?;
+		if rows.len() != height as usize {
+			return Err(SaveError::WrongRowCount { expected: height, found: rows.len() });
+		}
+		let mut data = Vec::<TileType>::new(); // Not allocated up front by the size, which can be broken.
+		for (line, row) in rows {
+			let tiles: Option<Vec<TileType>> = row.split_whitespace().map(parse_tile).collect();
+			match tiles {
+				Some(tiles) if tiles.len() == width as usize => data.extend(tiles),
+				_ => return Err(SaveError::InvalidValue { line })
+			}
+		}
+		let mut tilemap = TileMap::new(data, width, height, grid_size);
+		// Doors are created from the tiles, then get the saved state.
+		for (coord, (state, open_amount, timer)) in doors {
+			let door = tilemap.doors.get_mut(&coord).ok_or(SaveError::MissingField("door tile"))?;
+			(door.state, door.open_amount, door.timer) = (state, open_amount, timer);
+		}
+		tilemap.update_door_connections();
+		// Moving blocks set the tiles from the origin to the destination.
+		let size = glam::i64vec2(width as i64, height as i64);
+		let inside = |coord: glam::I64Vec2| coord.cmpge(glam::I64Vec2::ZERO).all() && coord.cmplt(size).all();
+		for (line, pushwall) in pushwalls {
+			let origin = pushwall.origin.as_i64vec2();
+			if !inside(origin) || !inside(origin + pushwall.direction.as_i64vec2() * pushwall.distance as i64) {
+				return Err(SaveError::InvalidValue { line });
+			}
+			tilemap.pushwalls.push(pushwall);
+		}
+		tilemap.animations = animations;
+
+		let player = EntityId(player.ok_or(SaveError::MissingField("player"))?);
+		if !entities.contains_key(&player) {
+			return Err(SaveError::MissingField("player entity"));
+		}
+		let next_id = next_id.ok_or(SaveError::MissingField("entities"))?;
+		if entities.keys().any(|id| id.0 >= next_id) {
+			return Err(SaveError::MissingField("entities"));
+		}
+		let [secrets_found, secrets_total] = secrets.ok_or(SaveError::MissingField("secrets"))?;
+		let [x, y, angle] = spawn.ok_or(SaveError::MissingField("spawn"))?;
+
+		let mut gameworld = GameWorld {
+			tilemap,
+			entities: Entities::restore(next_id, entities),
+			player,
+			player_stats,
+			player_spawn: Transform { position: glam::vec2(x, y), angle },
+			previous_transforms: BTreeMap::new(),
+			rng: rng.ok_or(SaveError::MissingField("rng"))?,
+			secrets_found,
+			secrets_total,
+			time,
+			events: Vec::new(),
+			pathfinder: PathFinder::new(Connectivity::Eight)
+		};
+		gameworld.update_path_blocked();
+		Ok(gameworld)
+	}
+}
+
+fn parse_values<T: FromStr, const N: usize>(line: usize, values: &[&str]) -> Result<[T; N], SaveError> {
+	if values.len() != N {
+		return Err(SaveError::InvalidValue { line });
+	}
+	let parsed: Vec<T> = values.iter()
+		.map(|v| v.parse::<T>().map_err(|_| SaveError::InvalidValue { line }))
+		.collect::<Result<_, _>>()?;
+	parsed.try_into().map_err(|_| SaveError::InvalidValue { line })
+}
+
+// Entity of the last entity line to add a component.
+fn entity_mut(entities: &mut BTreeMap<EntityId, Entity>, last_entity: Option<EntityId>, line: usize) -> Result<&mut Entity, SaveError> {
+	last_entity.and_then(|id| entities.get_mut(&id)).ok_or(SaveError::NoEntity { line })
+}
+
+fn enemy_state_code(state: EnemyState) -> &'static str {
+	match state {
+		EnemyState::Stand => "stand",
+		EnemyState::Patrol => "patrol",
+		EnemyState::Chase => "chase",
+		EnemyState::Attack => "attack",
+		EnemyState::Pain => "pain",
+		EnemyState::Die => "die",
+		EnemyState::Dead => "dead"
+	}
+}
+
+fn parse_enemy_state(code: &str) -> Option<EnemyState> {
+	[EnemyState::Stand, EnemyState::Patrol, EnemyState::Chase, EnemyState::Attack, EnemyState::Pain, EnemyState::Die, EnemyState::Dead]
+		.into_iter().find(|state| enemy_state_code(*state) == code)
+}
+
+
+#[test]
+fn savegame_round_trip() {
+	let mut gameworld = GameWorld::test_gameworld();
+	let guard = gameworld.entities.spawn(Entity::guard(&super::EnemySpawn { position: glam::vec2(650.0, 150.0), angle: 0.0, patrol: true }));
+	gameworld.entities.spawn(Entity::from_static(glam::uvec2(2, 2), &super::Static::pickup(1, super::Pickup::Key(1)), 100.0));
+	gameworld.tilemap.set_tile(glam::uvec2(2, 3), TileType::Gate(5, 1));
+	gameworld.player_stats.weapons.give(2);
+	gameworld.player_stats.keys.insert(3);
+	gameworld.player_stats.killer = Some(glam::vec2(1.5, 2.25));
+	gameworld.player_stats.armor = 7;
+	gameworld.tilemap.animations.insert(2, super::TextureAnimation { first: 8, count: 3, frame_duration: 0.3 });
+
+	// Open the door, start the push wall and run some ticks to get timers in the middle.
+	gameworld.player_mut().transform = Transform { position: glam::vec2(650.0, 340.0), angle: -std::f32::consts::FRAC_PI_2 };
+	assert!(gameworld.player_use());
+	gameworld.player_mut().transform = Transform { position: glam::vec2(450.0, 350.0), angle: 0.0 };
+	assert!(gameworld.player_use());
+	gameworld.player_fire(true);
+	for _ in 0..7 {
+		gameworld.tick(0.1);
+	}
+	gameworld.damage(guard, 1, None);
+	gameworld.take_events();
+	assert!(!gameworld.tilemap.pushwalls.is_empty());
+	assert_eq!(gameworld.tilemap.doors[&[6, 2]].state, DoorState::Opening);
+
+	let text = gameworld.to_save_string();
+	let loaded = GameWorld::from_save_string(&text).unwrap();
+	assert_eq!(loaded, gameworld);
+	assert_eq!(loaded.to_save_string(), text);
+
+	// Both continue the same way, including the random damage.
+	let (mut a, mut b) = (gameworld, loaded);
+	for _ in 0..10 {
+		a.player_fire(true);
+		b.player_fire(true);
+		a.tick(0.1);
+		b.tick(0.1);
+	}
+	assert_eq!(a, b);
+}
+
+#[test]
+fn savegame_errors() {
+	assert!(matches!(GameWorld::from_save_string("save 3\n"), Err(SaveError::UnsupportedVersion(3))));
+	assert!(matches!(GameWorld::from_save_string("save 0\n"), Err(SaveError::UnsupportedVersion(0))));
+	assert!(matches!(GameWorld::from_save_string("save 1\nhealth 10 10\n"), Err(SaveError::NoEntity { line: 2 })));
+	assert!(matches!(GameWorld::from_save_string("save 1\nrng 1\n"), Err(SaveError::MissingField("size"))));
+	assert!(matches!(GameWorld::from_save_string("save 2\nsize 4294967295 2\n"), Err(SaveError::InvalidValue { line: 2 })));
+	assert!(matches!(GameWorld::from_save_string("save 2\ngrid 0\n"), Err(SaveError::InvalidValue { line: 2 })));
+	assert!(matches!(GameWorld::from_save_string("save 2\npushwall 1 1 1 1 0 1 0\n"), Err(SaveError::InvalidValue { line: 2 })));
+	assert!(matches!(GameWorld::from_save_string("save 2\npushwall 1 1 1 0 0 9 0\n"), Err(SaveError::InvalidValue { line: 2 })));
+
+	// Push walls out of the map
+	let text = GameWorld::test_gameworld().to_save_string();
+	for pushwall in ["pushwall 7 2 1 0 3 2 0", "pushwall 9 2 -1 0 3 1 0"] {
+		let broken = text.replace("\nsize ", &format!("\n{}\nsize ", pushwall));
+		let Err(SaveError::InvalidValue { line }) = GameWorld::from_save_string(&broken) else {
+			panic!("{} is loaded", pushwall);
+		};
+		assert_eq!(broken.lines().nth(line - 1), Some(pushwall));
+	}
+}
+
+#[test]
+fn savegame_loads_version_1() {
+	let mut gameworld = GameWorld::test_gameworld();
+	gameworld.tick(0.1);
+	gameworld.take_events();
+	let text = gameworld.to_save_string().lines()
+		.filter(|line| !line.starts_with("time ") && !line.starts_with("anim "))
+		.map(|line| if line.starts_with("save ") { "save 1\n".to_string() } else { format!("{}\n", line) })
+		.collect::<String>();
+	let loaded = GameWorld::from_save_string(&text).unwrap();
+	assert_eq!(loaded.time, 0.0);
+	assert_eq!(loaded.tilemap, gameworld.tilemap);
+	assert!(loaded.to_save_string().contains("\nsave 2\n"));
+}
@@ -0,0 +1,35 @@
+// Sprite which has 8 rotation frames for actors.
+// Frames are the layers base_layer..base_layer+8 of a grid texture array.
+// Frame 0 is the front, and the next frames are seen as the viewer goes around the actor counterclockwise(in world angle) by 45 degrees.
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DirectionalSprite {
+	pub base_layer: u32
+}
+
+impl DirectionalSprite {
+	pub const FRAMES: u32 = 8;
+
+	// Texture layer to draw for the viewer.
+	pub fn layer(&self, position: glam::Vec2, facing: f32, viewer: glam::Vec2) -> u32 {
+		let to_viewer = viewer - position;
+		let diff = (to_viewer.y.atan2(to_viewer.x) - facing).rem_euclid(std::f32::consts::TAU);
+		let step = std::f32::consts::TAU / Self::FRAMES as f32;
+		self.base_layer + (diff / step).round() as u32 % Self::FRAMES
+	}
+}
+
+
+#[test]
+fn directional_sprite_layer() {
+	use std::f32::consts::{PI, FRAC_PI_2, FRAC_PI_4};
+	let sprite = DirectionalSprite { base_layer: 2 };
+	let position = glam::vec2(1.0, 1.0);
+	assert_eq!(sprite.layer(position, 0.0, glam::vec2(5.0, 1.0)), 2); // front
+	assert_eq!(sprite.layer(position, PI, glam::vec2(5.0, 1.0)), 6); // back
+	assert_eq!(sprite.layer(position, 0.0, glam::vec2(1.0, 5.0)), 4);
+	assert_eq!(sprite.layer(position, FRAC_PI_2, glam::vec2(5.0, 1.0)), 8);
+	assert_eq!(sprite.layer(position, 0.0, glam::vec2(5.0, 4.9)), 3);
+	assert_eq!(sprite.layer(position, -FRAC_PI_4 * 0.4, glam::vec2(5.0, 1.0)), 2);
+	assert_eq!(sprite.layer(position, FRAC_PI_4 * 0.4, glam::vec2(5.0, 1.0)), 2); // wraps around
+}
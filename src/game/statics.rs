@@ -0,0 +1,23 @@
+// Static objects(decorations and pickups) placed at the center of tiles.
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Pickup { Health(u32), Ammo(u32), Treasure(u32), Key(u32) }
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Static {
+	pub texid: u32,
+	pub blocking: bool,
+	pub pickup: Option<Pickup>
+}
+
+impl Static {
+	pub const RADIUS: f32 = 0.25; // ratio to grid size
+
+	pub fn decoration(texid: u32, blocking: bool) -> Self {
+		Self { texid, blocking, pickup: None }
+	}
+
+	pub fn pickup(texid: u32, pickup: Pickup) -> Self {
+		Self { texid, blocking: false, pickup: Some(pickup) }
+	}
+}
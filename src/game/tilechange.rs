@@ -0,0 +1,190 @@
+// Runtime tile editing and the change log of the tiles, so renderers upload only the changed tiles.
+// Every change gets a revision unique among all tile maps. A renderer keeps the last revision it uploaded
+// and asks get_dirty_since() for the tiles changed after it.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::{Door, TileMap, TileType};
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1); // 0 is never a revision, so it means "nothing uploaded yet".
+
+fn next_revision() -> u64 {
+	NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+// Inclusive rect of tile coords.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TileRect {
+	pub min: glam::UVec2,
+	pub max: glam::UVec2
+}
+
+impl TileRect {
+	pub fn union(&self, other: &TileRect) -> TileRect {
+		TileRect { min: self.min.min(other.min), max: self.max.max(other.max) }
+	}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TileDirty {
+	Clean,
+	Tiles(TileRect),
+	All // unknown revision, or the map is resized
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct TileChanges {
+	base: Option<u64>, // revision when the whole map was replaced. None: dropped from the log
+	log: VecDeque<(u64, TileRect)>
+}
+
+impl Default for TileChanges {
+	fn default() -> Self {
+		Self { base: Some(next_revision()), log: VecDeque::new() }
+	}
+}
+
+impl TileChanges {
+	const MAX_LOG: usize = 256; // Older revisions get TileDirty::All.
+}
+
+impl TileMap {
+	pub fn get_revision(&self) -> u64 {
+		// The log is never empty after the base is dropped.
+		self.changes.log.back().map(|(revision, _)| *revision).or(self.changes.base).unwrap_or_default()
+	}
+
+	// Tiles changed after the revision. Revision 0(nothing uploaded yet) is always TileDirty::All.
+	pub fn get_dirty_since(&self, revision: u64) -> TileDirty {
+		if revision == 0 {
+			return TileDirty::All;
+		}
+		if revision == self.get_revision() {
+			return TileDirty::Clean;
+		}
+		let start = match self.changes.log.iter().position(|(r, _)| *r == revision) {
+			Some(i) => i + 1,
+			None if Some(revision) == self.changes.base => 0,
+			None => return TileDirty::All
+		};
+		let rect = self.changes.log.iter().skip(start).map(|(_, rect)| *rect).reduce(|a, b| a.union(&b));
+		rect.map_or(TileDirty::Clean, TileDirty::Tiles)
+	}
+
+	// Record the change of the tiles in the rect, clamped to the map.
+	pub(super) fn mark_dirty(&mut self, min: glam::UVec2, max: glam::UVec2) {
+		let last = glam::uvec2(self.width, self.height).saturating_sub(glam::UVec2::ONE);
+		let rect = TileRect { min: min.min(last), max: max.min(last) };
+		if self.changes.log.len() >= TileChanges::MAX_LOG {
+			self.changes.log.pop_front();
+			self.changes.base = None; // The revisions before the log are unknown now.
+		}
+		self.changes.log.push_back((next_revision(), rect));
+	}
+
+	// Replace the tile. Door tiles get a closed door, and the door state of a replaced door tile is removed.
+	pub fn set_tile(&mut self, coord: glam::UVec2, tile: TileType) -> bool {
+		if coord.x >= self.width || coord.y >= self.height {
+			return false;
+		}
+		self.data[(coord.y * self.width + coord.x) as usize] = tile;
+		match tile {
+			TileType::Door(orientation, _) => {
+				self.doors.insert(coord.to_array(), Door::new(orientation));
+			},
+			_ => {
+				self.doors.remove(&coord.to_array());
+			}
+		}
+		// Door tiles around borrow the floor of this tile.
+		self.mark_dirty(coord.saturating_sub(glam::UVec2::ONE), coord + glam::UVec2::ONE);
+		self.areas = None;
+		true
+	}
+
+	// Resize keeping the tiles at the same coords. New tiles are filled with the tile.
+	pub fn resize(&mut self, width: u32, height: u32, fill: TileType) {
+		let data = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+			.map(|(x, y)| match x < self.width && y < self.height {
+				true => self.data[(y * self.width + x) as usize],
+				false => fill
+			}).collect();
+		let mut resized = TileMap::new(data, width, height, self.grid_size);
+		for (coord, door) in resized.doors.iter_mut() {
+			if let Some(old) = self.doors.get(coord) {
+				*door = old.clone();
+			}
+		}
+		resized.update_door_connections();
+		resized.pushwalls = std::mem::take(&mut self.pushwalls).into_iter()
+			.filter(|pushwall| pushwall.origin.cmplt(resized.size()).all() && pushwall.destination().cmplt(resized.size()).all())
+			.collect();
+		resized.animations = std::mem::take(&mut self.animations);
+		*self = resized;
+	}
+
+	fn size(&self) -> glam::UVec2 {
+		glam::uvec2(self.width, self.height)
+	}
+}
+
+
+#[test]
+fn tile_changes_since_revision() {
+	let mut tilemap = TileMap::test_tilemap();
+	let start = tilemap.get_revision();
+	assert_eq!(tilemap.get_dirty_since(start), TileDirty::Clean);
+	assert_eq!(tilemap.get_dirty_since(0), TileDirty::All);
+	assert_eq!(TileMap::test_tilemap().get_dirty_since(start), TileDirty::All); // Revision of another map
+
+	assert!(tilemap.set_tile(glam::uvec2(3, 2), TileType::Empty(1, 2)));
+	assert!(!tilemap.set_tile(glam::uvec2(8, 2), TileType::Empty(1, 2)));
+	let first = tilemap.get_revision();
+	assert_eq!(tilemap.get_dirty_since(start), TileDirty::Tiles(TileRect { min: glam::uvec2(2, 1), max: glam::uvec2(4, 3) }));
+	tilemap.set_tile(glam::uvec2(7, 7), TileType::Door(super::DoorOrientation::Vertical, 4));
+	assert!(tilemap.doors.contains_key(&[7, 7]));
+	assert_eq!(tilemap.get_dirty_since(first), TileDirty::Tiles(TileRect { min: glam::uvec2(6, 6), max: glam::uvec2(7, 7) }));
+	assert_eq!(tilemap.get_dirty_since(start), TileDirty::Tiles(TileRect { min: glam::uvec2(2, 1), max: glam::uvec2(7, 7) }));
+	tilemap.set_tile(glam::uvec2(7, 7), TileType::wall(0));
+	assert!(!tilemap.doors.contains_key(&[7, 7]));
+
+	// Door animation and push walls are changes too.
+	let revision = tilemap.get_revision();
+	tilemap.doors.get_mut(&[6, 2]).unwrap().use_door();
+	tilemap.tick_doors(0.1, |_| false);
+	assert_eq!(tilemap.get_dirty_since(revision), TileDirty::Tiles(TileRect { min: glam::uvec2(6, 2), max: glam::uvec2(6, 2) }));
+	let revision = tilemap.get_revision();
+	assert!(tilemap.push_wall(glam::uvec2(5, 3), glam::IVec2::X));
+	tilemap.tick_pushwalls(10.0, |_| false);
+	assert_eq!(tilemap.data[3 * 8 + 6], TileType::wall(3));
+	assert!(matches!(tilemap.get_dirty_since(revision), TileDirty::Tiles(rect) if rect.min.x <= 5 && rect.max.x >= 6));
+
+	// Too old revision
+	for _ in 0..TileChanges::MAX_LOG {
+		tilemap.set_tile(glam::uvec2(1, 1), TileType::Empty(0, 0));
+	}
+	assert_eq!(tilemap.get_dirty_since(start), TileDirty::All);
+	assert_eq!(tilemap.get_dirty_since(revision), TileDirty::All);
+	assert_eq!(tilemap.get_dirty_since(0), TileDirty::All); // Nothing uploaded yet
+	assert_eq!(tilemap.get_dirty_since(tilemap.get_revision()), TileDirty::Clean);
+}
+
+#[test]
+fn tilemap_resize() {
+	let mut tilemap = TileMap::test_tilemap();
+	tilemap.animations.insert(2, super::TextureAnimation { first: 8, count: 3, frame_duration: 0.3 });
+	let revision = tilemap.get_revision();
+	tilemap.resize(10, 6, TileType::wall(1));
+	assert_eq!((tilemap.width, tilemap.height), (10, 6));
+	assert_eq!(tilemap.get_dirty_since(revision), TileDirty::All);
+	assert_eq!(tilemap.get_tile(glam::uvec2(1, 1)), Some(&TileType::Empty(0, 1)));
+	assert_eq!(tilemap.get_tile(glam::uvec2(9, 1)), Some(&TileType::wall(1)));
+	assert_eq!(tilemap.get_tile(glam::uvec2(1, 6)), None);
+	assert!(tilemap.doors.contains_key(&[6, 2]));
+	assert_eq!(tilemap.animations.get_frame(2, 0.3), 9); // Still animated
+
+	tilemap.resize(5, 5, TileType::wall(1));
+	assert!(tilemap.doors.is_empty());
+	assert_eq!(tilemap.data.len(), 25);
+}
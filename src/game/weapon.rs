@@ -0,0 +1,274 @@
+// Weapon definitions, the weapons held by the player and hitscan firing.
+
+use std::collections::BTreeMap;
+
+use super::{collision, EntityId, GameWorld, RaycastHit};
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AmmoType { Bullet }
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WeaponDef {
+	pub name: &'static str,
+	pub fire_interval: f32, // seconds between shots
+	pub damage: (i32, i32), // inclusive range
+	pub range: f32, // ratio to grid size
+	pub ammo: Option<AmmoType>, // None: no ammo needed
+	pub automatic: bool, // false: fires once per trigger pull
+	pub view_layer: u32 // first layer of the view model frames(idle, firing...)
+}
+
+pub const WEAPONS: [WeaponDef; 4] = [
+	WeaponDef { name: "knife", fire_interval: 0.4, damage: (5, 15), range: 0.8, ammo: None, automatic: false, view_layer: 0 },
+	WeaponDef { name: "pistol", fire_interval: 0.35, damage: (10, 25), range: 64.0, ammo: Some(AmmoType::Bullet), automatic: false, view_layer: 2 },
+	WeaponDef { name: "machine gun", fire_interval: 0.15, damage: (10, 25), range: 64.0, ammo: Some(AmmoType::Bullet), automatic: true, view_layer: 4 },
+	WeaponDef { name: "chain gun", fire_interval: 0.075, damage: (10, 25), range: 64.0, ammo: Some(AmmoType::Bullet), automatic: true, view_layer: 6 }
+];
+
+// Frame of the held weapon drawn over the first-person view.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ViewModelFrame {
+	pub layer: u32,
+	pub lowered: f32 // 0: raised, 1: out of the screen
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Weapons {
+	pub owned: [bool; WEAPONS.len()],
+	pub current: usize, // index of WEAPONS
+	pub ammo: BTreeMap<AmmoType, u32>,
+	pub cooldown: f32, // seconds until the next shot
+	pub switching: f32, // seconds left to raise the current weapon
+	pub(super) trigger_held: bool
+}
+
+impl Default for Weapons {
+	fn default() -> Self {
+		Self {
+			owned: [true, true, false, false],
+			current: 1,
+			ammo: BTreeMap::from([(AmmoType::Bullet, 8)]),
+			cooldown: 0.0,
+			switching: 0.0,
+			trigger_held: false
+		}
+	}
+}
+
+impl Weapons {
+	pub const SWITCH_TIME: f32 = 0.3;
+	pub const MAX_AMMO: u32 = 99;
+	pub const VIEW_FIRE_FRAMES: u32 = 1; // frames after the idle frame, played in the first half of the fire interval
+
+	pub fn get_current(&self) -> &'static WeaponDef {
+		&WEAPONS[self.current]
+	}
+
+	pub fn get_ammo(&self, ammo_type: AmmoType) -> u32 {
+		self.ammo.get(&ammo_type).copied().unwrap_or(0)
+	}
+
+	pub fn add_ammo(&mut self, ammo_type: AmmoType, amount: u32) {
+		let ammo = self.ammo.entry(ammo_type).or_insert(0);
+		*ammo = ammo.saturating_add(amount).min(Self::MAX_AMMO);
+	}
+
+	pub fn give(&mut self, slot: usize) {
+		if let Some(owned) = self.owned.get_mut(slot) {
+			*owned = true;
+		}
+	}
+
+	fn can_fire(&self, slot: usize) -> bool {
+		self.owned[slot] && WEAPONS[slot].ammo.is_none_or(|ammo_type| self.get_ammo(ammo_type) > 0)
+	}
+
+	// Returns true if switching to the weapon starts.
+	pub fn select(&mut self, slot: usize) -> bool {
+		if slot >= WEAPONS.len() || slot == self.current || !self.can_fire(slot) {
+			return false;
+		}
+		self.current = slot;
+		self.cooldown = 0.0;
+		self.switching = Self::SWITCH_TIME;
+		true
+	}
+
+	// The new weapon is raised from the bottom while switching. Firing plays the frames after the idle frame.
+	pub fn get_view_frame(&self) -> ViewModelFrame {
+		let weapon = self.get_current();
+		let lowered = self.switching / Self::SWITCH_TIME;
+		let firing = 1.0 - self.cooldown / weapon.fire_interval; // progress of the fire interval
+		let frame = if self.cooldown > 0.0 && firing < 0.5 {
+			1 + (firing * 2.0 * Self::VIEW_FIRE_FRAMES as f32) as u32
+		} else {
+			0
+		};
+		ViewModelFrame { layer: weapon.view_layer + frame, lowered }
+	}
+
+	pub fn tick(&mut self, delta: f32) {
+		self.cooldown = (self.cooldown - delta).max(0.0);
+		self.switching = (self.switching - delta).max(0.0);
+	}
+
+	// Called every tick with the trigger state. Returns the weapon fired, and its ammo is consumed.
+	pub fn fire(&mut self, trigger: bool) -> Option<&'static WeaponDef> {
+		let pulled = trigger && (!self.trigger_held || self.get_current().automatic);
+		self.trigger_held = trigger;
+		if !pulled || self.cooldown > 0.0 || self.switching > 0.0 || !self.can_fire(self.current) {
+			return None;
+		}
+		let weapon = self.get_current();
+		if let Some(ammo) = weapon.ammo.and_then(|ammo_type| self.ammo.get_mut(&ammo_type)) {
+			*ammo -= 1;
+		}
+		self.cooldown = weapon.fire_interval;
+
+		// Out of ammo. Fall back to the best weapon that can fire.
+		if !self.can_fire(self.current) {
+			if let Some(slot) = (0..WEAPONS.len()).rev().find(|slot| self.can_fire(*slot)) {
+				self.select(slot);
+			}
+		}
+		Some(weapon)
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum HitscanHit {
+	Wall(RaycastHit),
+	Entity { id: EntityId, point: glam::Vec2, distance: f32 } // world space
+}
+
+impl GameWorld {
+	// Trace the ray through the tile map. Stops at the first wall or the nearest circle of the alive entity with health.
+	pub fn hitscan(&self, origin: glam::Vec2, direction: glam::Vec2, max_distance: f32, shooter: Option<EntityId>) -> Option<HitscanHit> {
+		let dir = direction.normalize_or_zero();
+		let wall = self.tilemap.raycast(origin, dir, max_distance);
+		let max_distance = wall.as_ref().map_or(max_distance, |hit| hit.distance);
+		self.entities.iter()
+			.filter(|(id, entity)| Some(*id) != shooter && entity.health.is_some() && entity.is_alive())
+			.filter_map(|(id, entity)| entity.collider
+				.and_then(|c| collision::ray_circle_distance(origin, dir, entity.transform.position, c.radius))
+				.map(|distance| (id, distance)))
+			.filter(|(_, distance)| *distance <= max_distance)
+			.min_by(|a, b| a.1.total_cmp(&b.1))
+			.map(|(id, distance)| HitscanHit::Entity { id, point: origin + dir * distance, distance })
+			.or(wall.map(HitscanHit::Wall))
+	}
+
+	pub fn get_player_weapons(&self) -> &Weapons {
+		&self.player_stats.weapons
+	}
+
+	// Returns true if switching to the weapon starts.
+	pub fn player_select_weapon(&mut self, slot: usize) -> bool {
+		self.player_stats.is_alive() && self.player_stats.weapons.select(slot)
+	}
+
+	// Called every tick with the trigger state. Returns the hit if the weapon is fired.
+	pub fn player_fire(&mut self, trigger: bool) -> Option<HitscanHit> {
+		let trigger = trigger && self.player_stats.is_alive();
+		let weapon = self.player_stats.weapons.fire(trigger)?;
+		let origin = self.get_player_position();
+		let range = weapon.range * self.tilemap.grid_size;
+		if weapon.ammo.is_some() { // Knife is silent.
+			self.make_noise(origin);
+		}
+		let hit = self.hitscan(origin, self.get_player_forward_vector(), range, Some(self.player));
+		if let Some(HitscanHit::Entity { id, .. }) = hit {
+			let damage = self.random_range(weapon.damage.0, weapon.damage.1);
+			self.damage(id, damage, Some(origin));
+		}
+		hit
+	}
+}
+
+
+#[test]
+fn weapons_fire_ammo_and_switch() {
+	let mut weapons = Weapons::default();
+	assert_eq!(weapons.get_current().name, "pistol");
+	assert!(!weapons.select(2)); // Not owned
+	assert!(!weapons.select(1));
+
+	// Semi automatic fires once per trigger pull.
+	assert!(weapons.fire(true).is_some());
+	assert_eq!(weapons.get_ammo(AmmoType::Bullet), 7);
+	weapons.tick(1.0);
+	assert!(weapons.fire(true).is_none());
+	assert!(weapons.fire(false).is_none());
+	assert!(weapons.fire(true).is_some());
+
+	// Automatic fires while the trigger is held, after switching.
+	weapons.give(2);
+	assert!(weapons.select(2));
+	assert!(weapons.fire(true).is_none());
+	weapons.tick(Weapons::SWITCH_TIME);
+	assert!(weapons.fire(true).is_some());
+	assert!(weapons.fire(true).is_none()); // Cooldown
+	weapons.tick(WEAPONS[2].fire_interval);
+	assert!(weapons.fire(true).is_some());
+	assert_eq!(weapons.get_ammo(AmmoType::Bullet), 4);
+
+	// Falls back to the knife without ammo.
+	for _ in 0..4 {
+		weapons.tick(1.0);
+		assert!(weapons.fire(true).is_some());
+	}
+	assert_eq!(weapons.get_ammo(AmmoType::Bullet), 0);
+	assert_eq!(weapons.get_current().name, "knife");
+	assert!(!weapons.select(1));
+	weapons.add_ammo(AmmoType::Bullet, 200);
+	assert_eq!(weapons.get_ammo(AmmoType::Bullet), Weapons::MAX_AMMO);
+	weapons.add_ammo(AmmoType::Bullet, u32::MAX);
+	assert_eq!(weapons.get_ammo(AmmoType::Bullet), Weapons::MAX_AMMO);
+	assert!(weapons.select(1));
+}
+
+#[test]
+fn weapon_view_frames() {
+	let mut weapons = Weapons::default();
+	let pistol = &WEAPONS[1];
+	assert_eq!(weapons.get_view_frame(), ViewModelFrame { layer: pistol.view_layer, lowered: 0.0 });
+
+	weapons.fire(true);
+	assert_eq!(weapons.get_view_frame().layer, pistol.view_layer + 1);
+	weapons.tick(pistol.fire_interval * 0.6);
+	assert_eq!(weapons.get_view_frame().layer, pistol.view_layer);
+
+	assert!(weapons.select(0));
+	assert_eq!(weapons.get_view_frame(), ViewModelFrame { layer: WEAPONS[0].view_layer, lowered: 1.0 });
+	weapons.tick(Weapons::SWITCH_TIME * 0.5);
+	assert!((weapons.get_view_frame().lowered - 0.5).abs() < 1e-4);
+}
+
+#[test]
+fn hitscan_stops_at_walls_and_hits_nearest() {
+	use super::{Entity, EnemySpawn, EnemyState};
+
+	let mut gameworld = GameWorld::test_gameworld();
+	let guard = |x: f32, y: f32| Entity::guard(&EnemySpawn { position: glam::vec2(x, y), angle: 0.0, patrol: false });
+	let near = gameworld.entities.spawn(guard(350.0, 150.0));
+	gameworld.entities.spawn(guard(550.0, 150.0));
+	gameworld.entities.spawn(guard(450.0, 550.0)); // behind the wall (3, 4)
+	gameworld.entities.flush();
+	let origin = glam::vec2(150.0, 150.0);
+	gameworld.player_mut().transform = super::Transform { position: origin, angle: 0.0 };
+	let player = Some(gameworld.player);
+
+	let hit = gameworld.hitscan(origin, glam::Vec2::X, 1000.0, player);
+	assert_eq!(hit, Some(HitscanHit::Entity { id: near, point: glam::vec2(325.0, 150.0), distance: 175.0 }));
+	let hit = gameworld.hitscan(origin, glam::vec2(300.0, 400.0), 1000.0, player);
+	assert!(matches!(hit, Some(HitscanHit::Wall(_))));
+	assert!(gameworld.hitscan(origin, glam::Vec2::X, 100.0, player).is_none());
+	assert!(matches!(gameworld.hitscan(origin, glam::Vec2::X, 100.0, None), Some(HitscanHit::Entity { distance: 0.0, .. })));
+
+	// Player shot hurts the guard.
+	assert!(matches!(gameworld.player_fire(true), Some(HitscanHit::Entity { id, .. }) if id == near));
+	let guard = gameworld.entities.get(near).unwrap();
+	assert!(guard.health.unwrap().current < guard.health.unwrap().max);
+	assert!(matches!(guard.ai.as_ref().unwrap().state, EnemyState::Pain | EnemyState::Die));
+	assert_eq!(gameworld.get_player_weapons().get_ammo(AmmoType::Bullet), 7);
+}
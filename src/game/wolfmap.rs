@@ -0,0 +1,417 @@
+/*
+
+Wolfenstein 3D map importer. (MAPHEAD + GAMEMAPS, "TED5v1.0")
+
+MAPHEAD
+ - u16 RLEW tag
+ - i32 x 100 level header offsets into GAMEMAPS (0 or -1 = no level)
+
+GAMEMAPS level header (at the offset from MAPHEAD)
+ - i32 x 3 plane start offsets
+ - u16 x 3 plane compressed lengths
+ - u16 width, u16 height
+ - [u8; 16] name (null terminated)
+
+Each plane is Carmack compressed, and the Carmack expanded data is RLEW compressed.
+Both begin with u16 expanded length in bytes.
+
+ */
+
+use std::{collections::BTreeMap, path::Path};
+
+use super::{MapData, TileMap, TileType, DoorOrientation};
+
+#[derive(Debug)]
+pub enum WolfMapError {
+	OpenFileFailed(std::io::Error),
+	InvalidMapHead,
+	LevelNotFound(usize),
+	TruncatedData,
+	InvalidPlaneSize
+}
+
+impl std::fmt::Display for WolfMapError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			WolfMapError::OpenFileFailed(e) => write!(f, "failed to open map data: {}", e),
+			WolfMapError::InvalidMapHead => write!(f, "invalid MAPHEAD"),
+			WolfMapError::LevelNotFound(i) => write!(f, "level {} not found", i),
+			WolfMapError::TruncatedData => write!(f, "unexpected end of GAMEMAPS data"),
+			WolfMapError::InvalidPlaneSize => write!(f, "plane size does not match level size")
+		}
+	}
+}
+
+impl std::error::Error for WolfMapError {}
+
+const NEARTAG: u8 = 0xa7;
+const FARTAG: u8 = 0xa8;
+const MAX_LEVELS: usize = 100;
+const LEVEL_HEADER_SIZE: usize = 38;
+
+// plane 0
+const WALL_CODES: std::ops::Range<u16> = 1..90;
+const DOOR_CODES: std::ops::RangeInclusive<u16> = 90..=101;
+const LOCKED_DOOR_CODES: std::ops::RangeInclusive<u16> = 92..=95; // gold(92, 93), silver(94, 95)
+// 102..=105 are not walls, 106 is AMBUSHTILE(floor marker) and AREATILE(107) and above are floors.
+
+// plane 1
+const PLAYER_START_CODES: std::ops::RangeInclusive<u16> = 19..=22;
+const STATIC_CODES: std::ops::RangeInclusive<u16> = 23..=74;
+const PUSHWALL_CODE: u16 = 98;
+const TURN_POINT_CODES: std::ops::RangeInclusive<u16> = 90..=97;
+const ENEMY_CODES: std::ops::RangeInclusive<u16> = 108..=255;
+
+struct Reader<'a> {
+	data: &'a [u8],
+	pos: usize
+}
+
+impl<'a> Reader<'a> {
+	fn new(data: &'a [u8]) -> Self {
+		Self { data, pos: 0 }
+	}
+	fn at(data: &'a [u8], pos: usize) -> Self {
+		Self { data, pos }
+	}
+	fn bytes(&mut self, len: usize) -> Result<&'a [u8], WolfMapError> {
+		let end = self.pos.checked_add(len).ok_or(WolfMapError::TruncatedData)?;
+		let bytes = self.data.get(self.pos..end).ok_or(WolfMapError::TruncatedData)?;
+		self.pos = end;
+		Ok(bytes)
+	}
+	fn u8(&mut self) -> Result<u8, WolfMapError> {
+		Ok(self.bytes(1)?[0])
+	}
+	fn u16(&mut self) -> Result<u16, WolfMapError> {
+		let b = self.bytes(2)?;
+		Ok(u16::from_le_bytes([b[0], b[1]]))
+	}
+	fn i32(&mut self) -> Result<i32, WolfMapError> {
+		let b = self.bytes(4)?;
+		Ok(i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+	}
+}
+
+// Port of CAL_CarmackExpand. Returns expanded words.
+pub fn carmack_expand(source: &[u8]) -> Result<Vec<u16>, WolfMapError> {
+	let mut reader = Reader::new(source);
+	let length = reader.u16()? as usize / 2;
+	let mut dest = Vec::<u16>::with_capacity(length);
+
+	while dest.len() < length {
+		let ch = reader.u16()?;
+		let [count, tag] = ch.to_le_bytes();
+		if tag == NEARTAG || tag == FARTAG {
+			if count == 0 { // escaped word which has tag byte in high byte
+				dest.push(ch | reader.u8()? as u16);
+				continue;
+			}
+			let start = if tag == NEARTAG {
+				let offset = reader.u8()? as usize;
+				dest.len().checked_sub(offset).ok_or(WolfMapError::TruncatedData)?
+			} else {
+				reader.u16()? as usize
+			};
+			for i in start..start + count as usize {
+				let word = *dest.get(i).ok_or(WolfMapError::TruncatedData)?;
+				dest.push(word);
+			}
+		} else {
+			dest.push(ch);
+		}
+	}
+	dest.truncate(length);
+	Ok(dest)
+}
+
+// Port of CA_RLEWexpand. Source is carmack expanded words including the first length word.
+pub fn rlew_expand(source: &[u16], rlew_tag: u16) -> Result<Vec<u16>, WolfMapError> {
+	let mut words = source.iter().copied();
+	let mut next = || words.next().ok_or(WolfMapError::TruncatedData);
+	let length = next()? as usize / 2;
+	let mut dest = Vec::<u16>::with_capacity(length);
+
+	while dest.len() < length {
+		let value = next()?;
+		if value != rlew_tag {
+			dest.push(value);
+		} else {
+			let count = next()? as usize;
+			let value = next()?;
+			dest.extend(std::iter::repeat_n(value, count));
+		}
+	}
+	dest.truncate(length);
+	Ok(dest)
+}
+
+pub struct WolfMaps {
+	rlew_tag: u16,
+	header_offsets: Vec<i32>,
+	gamemaps: Vec<u8>
+}
+
+pub struct WolfLevel {
+	pub name: String,
+	pub width: u16,
+	pub height: u16,
+	pub planes: [Vec<u16>; 2] // 0: walls, 1: objects
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WolfObjectKind {
+	Static(u16),
+	PushWall,
+	TurnPoint,
+	Enemy,
+	Unknown
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WolfObject {
+	pub tile: glam::UVec2,
+	pub code: u16,
+	pub kind: WolfObjectKind
+}
+
+pub struct WolfImport {
+	pub map: MapData,
+	pub objects: Vec<WolfObject>
+}
+
+impl WolfMaps {
+	pub fn load(maphead_path: impl AsRef<Path>, gamemaps_path: impl AsRef<Path>) -> Result<Self, WolfMapError> {
+		let maphead = std::fs::read(maphead_path).map_err(WolfMapError::OpenFileFailed)?;
+		let gamemaps = std::fs::read(gamemaps_path).map_err(WolfMapError::OpenFileFailed)?;
+		Self::from_bytes(&maphead, gamemaps)
+	}
+
+	pub fn from_bytes(maphead: &[u8], gamemaps: Vec<u8>) -> Result<Self, WolfMapError> {
+		let mut reader = Reader::new(maphead);
+		let rlew_tag = reader.u16().map_err(|_| WolfMapError::InvalidMapHead)?;
+		let mut header_offsets = Vec::<i32>::new();
+		// Some MAPHEADs are cut after the last level, so stop at the end of data.
+		while header_offsets.len() < MAX_LEVELS {
+			match reader.i32() {
+				Ok(offset) => header_offsets.push(offset),
+				Err(_) => break
+			}
+		}
+		if header_offsets.is_empty() {
+			return Err(WolfMapError::InvalidMapHead);
+		}
+		Ok(Self { rlew_tag, header_offsets, gamemaps })
+	}
+
+	pub fn level_count(&self) -> usize {
+		self.header_offsets.len()
+	}
+
+	pub fn level(&self, index: usize) -> Result<WolfLevel, WolfMapError> {
+		let offset = match self.header_offsets.get(index) {
+			Some(&offset) if offset > 0 => offset as usize,
+			_ => return Err(WolfMapError::LevelNotFound(index))
+		};
+		if offset + LEVEL_HEADER_SIZE > self.gamemaps.len() {
+			return Err(WolfMapError::TruncatedData);
+		}
+
+		let mut reader = Reader::at(&self.gamemaps, offset);
+		let plane_starts = [reader.i32()?, reader.i32()?, reader.i32()?];
+		let plane_lengths = [reader.u16()?, reader.u16()?, reader.u16()?];
+		let width = reader.u16()?;
+		let height = reader.u16()?;
+		let name_bytes = reader.bytes(16)?;
+		let name_len = name_bytes.iter().position(|&b| b == 0).unwrap_or(name_bytes.len());
+		let name = String::from_utf8_lossy(&name_bytes[..name_len]).into_owned();
+
+		let plane = |i: usize| -> Result<Vec<u16>, WolfMapError> {
+			let start = usize::try_from(plane_starts[i]).map_err(|_| WolfMapError::TruncatedData)?;
+			let compressed = Reader::at(&self.gamemaps, start).bytes(plane_lengths[i] as usize)?;
+			let expanded = rlew_expand(&carmack_expand(compressed)?, self.rlew_tag)?;
+			if expanded.len() != width as usize * height as usize {
+				return Err(WolfMapError::InvalidPlaneSize);
+			}
+			Ok(expanded)
+		};
+
+		Ok(WolfLevel { name, width, height, planes: [plane(0)?, plane(1)?] })
+	}
+}
+
+impl WolfLevel {
+	// Wall code n(1..90) becomes TileType::Wall(n - 1).
+	// Door code 90 + 2n(vertical), 91 + 2n(horizontal) becomes TileType::Door(_, n).
+	// Gold and silver locked door codes become TileType::Gate(n, key id 0 or 1).
+	// Wall with push wall object(98) becomes TileType::PushWall(n - 1).
+	// Floor(area), ambush and other codes become `empty`, because Wolf3D has no floor/ceiling textures.
+	pub fn to_tilemap(&self, grid_size: f32, empty: TileType) -> TileMap {
+		let data = self.planes[0].iter().zip(self.planes[1].iter()).map(|(&code, &object)| match code {
+			c if LOCKED_DOOR_CODES.contains(&c) => {
+				let offset = c - DOOR_CODES.start();
+				TileType::Gate(offset as u32 / 2, (c - LOCKED_DOOR_CODES.start()) as u32 / 2)
+			},
+			c if DOOR_CODES.contains(&c) => {
+				let offset = c - DOOR_CODES.start();
+				let orientation = if offset.is_multiple_of(2) { DoorOrientation::Vertical } else { DoorOrientation::Horizontal };
+				TileType::Door(orientation, offset as u32 / 2)
+			},
+			c if WALL_CODES.contains(&c) && object == PUSHWALL_CODE => TileType::PushWall(c as u32 - 1),
+			c if WALL_CODES.contains(&c) => TileType::wall(c as u32 - 1),
+			_ => empty
+		}).collect();
+
+		TileMap::new(data, self.width as u32, self.height as u32, grid_size)
+	}
+
+	pub fn objects(&self) -> Vec<WolfObject> {
+		self.planes[1].iter().enumerate().filter(|(_, &code)| code != 0).filter_map(|(i, &code)| {
+			let kind = match code {
+				c if PLAYER_START_CODES.contains(&c) => return None,
+				c if STATIC_CODES.contains(&c) => WolfObjectKind::Static(c - STATIC_CODES.start()),
+				PUSHWALL_CODE => WolfObjectKind::PushWall,
+				c if TURN_POINT_CODES.contains(&c) => WolfObjectKind::TurnPoint,
+				c if ENEMY_CODES.contains(&c) => WolfObjectKind::Enemy,
+				_ => WolfObjectKind::Unknown
+			};
+			let tile = glam::uvec2(i as u32 % self.width as u32, i as u32 / self.width as u32);
+			Some(WolfObject { tile, code, kind })
+		}).collect()
+	}
+
+	// Returns tile coord and angle(radian) of the player start. North is -Y in tile coord.
+	pub fn player_start(&self) -> Option<(glam::UVec2, f32)> {
+		let (i, code) = self.planes[1].iter().enumerate().find(|(_, c)| PLAYER_START_CODES.contains(c))?;
+		let angle = match code - PLAYER_START_CODES.start() {
+			0 => -std::f32::consts::FRAC_PI_2, // north
+			1 => 0.0, // east
+			2 => std::f32::consts::FRAC_PI_2, // south
+			_ => std::f32::consts::PI // west
+		};
+		Some((glam::uvec2(i as u32 % self.width as u32, i as u32 / self.width as u32), angle))
+	}
+
+	pub fn import(&self, grid_size: f32, empty: TileType) -> WolfImport {
+		let (start_tile, player_angle) = self.player_start().unwrap_or((glam::UVec2::ONE, 0.0));
+		WolfImport {
+			map: MapData {
+				tilemap: self.to_tilemap(grid_size, empty),
+				player_position: (start_tile.as_vec2() + 0.5) * grid_size,
+				player_angle,
+				statics: BTreeMap::new(), // NOTE: Static objects and enemies are left in `objects` as raw records.
+				enemies: Vec::new()
+			},
+			objects: self.objects()
+		}
+	}
+}
+
+
+// Test helpers: build GAMEMAPS with uncompressed(literal only) carmack data.
+#[cfg(test)]
+fn test_plane_bytes(words: &[u16], rlew_tag: u16) -> Vec<u8> {
+	// RLEW: compress all runs longer than 3 words.
+	let mut rlew = vec![(words.len() * 2) as u16];
+	let mut i = 0;
+	while i < words.len() {
+		let run = words[i..].iter().take_while(|&&w| w == words[i]).count();
+		if run > 3 || words[i] == rlew_tag {
+			rlew.extend([rlew_tag, run as u16, words[i]]);
+		} else {
+			rlew.extend(std::iter::repeat_n(words[i], run));
+		}
+		i += run;
+	}
+	let mut bytes = ((rlew.len() * 2) as u16).to_le_bytes().to_vec();
+	bytes.extend(rlew.iter().flat_map(|w| w.to_le_bytes()));
+	bytes
+}
+
+#[cfg(test)]
+fn test_wolfmaps(width: u16, height: u16, plane0: &[u16], plane1: &[u16]) -> WolfMaps {
+	const RLEW_TAG: u16 = 0xabcd;
+	let mut gamemaps = b"TED5v1.0".to_vec();
+	let planes = [test_plane_bytes(plane0, RLEW_TAG), test_plane_bytes(plane1, RLEW_TAG), test_plane_bytes(&vec![0; plane0.len()], RLEW_TAG)];
+	let mut starts = [0i32; 3];
+	for (i, plane) in planes.iter().enumerate() {
+		starts[i] = gamemaps.len() as i32;
+		gamemaps.extend(plane);
+	}
+	let header_offset = gamemaps.len() as i32;
+	starts.iter().for_each(|s| gamemaps.extend(s.to_le_bytes()));
+	planes.iter().for_each(|p| gamemaps.extend((p.len() as u16).to_le_bytes()));
+	gamemaps.extend(width.to_le_bytes());
+	gamemaps.extend(height.to_le_bytes());
+	gamemaps.extend(b"Test Level\0\0\0\0\0\0");
+
+	let mut maphead = RLEW_TAG.to_le_bytes().to_vec();
+	maphead.extend(header_offset.to_le_bytes());
+	maphead.extend(0i32.to_le_bytes());
+	WolfMaps::from_bytes(&maphead, gamemaps).unwrap()
+}
+
+#[test]
+fn wolfmap_carmack_expand() {
+	// 3 words expanded: 0x1234, near copy(2 words back 1) -> 0x1234 0x1234, escaped 0xa700 | 0x05
+	let source = [
+		0x0a, 0x00, // 5 words
+		0x34, 0x12,
+		0x02, NEARTAG, 0x01,
+		0x00, NEARTAG, 0x05,
+		0x01, FARTAG, 0x00, 0x00 // far copy 1 word from 0
+	];
+	assert_eq!(carmack_expand(&source).unwrap(), vec![0x1234, 0x1234, 0x1234, 0xa705, 0x1234]);
+	assert!(matches!(carmack_expand(&source[..6]), Err(WolfMapError::TruncatedData)));
+}
+
+#[test]
+fn wolfmap_rlew_expand() {
+	let source = [10, 1, 0xabcd, 3, 7, 2];
+	assert_eq!(rlew_expand(&source, 0xabcd).unwrap(), vec![1, 7, 7, 7, 2]);
+}
+
+#[test]
+fn wolfmap_import_level() {
+	let plane0 = [
+		1, 1,   92,  2,
+		1, 107, 90,  2,
+		1, 107, 106, 2,
+		3, 3,   3,   3
+	];
+	let plane1 = [
+		0, 0,  0,  0,
+		0, 20, 0,  98,
+		0, 98, 25, 0,
+		0, 0,  0,  0
+	];
+	let wolfmaps = test_wolfmaps(4, 4, &plane0, &plane1);
+	assert_eq!(wolfmaps.level_count(), 2);
+	assert!(matches!(wolfmaps.level(1), Err(WolfMapError::LevelNotFound(1))));
+
+	let level = wolfmaps.level(0).unwrap();
+	assert_eq!(level.name, "Test Level");
+	assert_eq!(level.planes[0], plane0);
+
+	let import = level.import(100.0, TileType::Empty(0, 1));
+	let tilemap = &import.map.tilemap;
+	assert_eq!(tilemap.width, 4);
+	assert_eq!(tilemap.data[0], TileType::wall(0));
+	assert_eq!(tilemap.data[3], TileType::wall(1));
+	assert_eq!(tilemap.data[2], TileType::Gate(1, 0)); // gold locked door
+	assert_eq!(tilemap.data[7], TileType::PushWall(1));
+	assert_eq!(tilemap.data[9], TileType::Empty(0, 1)); // push wall object on floor
+	assert_eq!(tilemap.data[5], TileType::Empty(0, 1));
+	assert_eq!(tilemap.data[10], TileType::Empty(0, 1)); // ambush tile
+	assert_eq!(tilemap.data[6], TileType::Door(DoorOrientation::Vertical, 0));
+	assert!(tilemap.doors.contains_key(&[2, 1]));
+	assert_eq!(tilemap.data[15], TileType::wall(2));
+
+	assert_eq!(import.map.player_position, glam::vec2(150.0, 150.0));
+	assert_eq!(import.map.player_angle, 0.0);
+	assert_eq!(import.objects, vec![
+		WolfObject { tile: glam::uvec2(3, 1), code: 98, kind: WolfObjectKind::PushWall },
+		WolfObject { tile: glam::uvec2(1, 2), code: 98, kind: WolfObjectKind::PushWall },
+		WolfObject { tile: glam::uvec2(2, 2), code: 25, kind: WolfObjectKind::Static(2) }
+	]);
+}
@@ -4,18 +4,24 @@ use winit::keyboard::KeyCode;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Action {
-	MoveForward, MoveBackward, StrafeLeft, StrafeRight, ToggleMinimap
+	MoveForward, MoveBackward, StrafeLeft, StrafeRight, ToggleMinimap, Use, Fire, SelectWeapon(usize),
+	QuickSave, QuickLoad, SelectSaveSlot(usize)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+	Left, Right
 }
 
 #[derive(Default)]
 struct MouseState {
 	pub relative_x: f32,
-	pub left_pressed: bool,
-	pub right_pressed: bool
+	pub button_state: HashMap<MouseButton, u32>
 }
 
 pub struct InputState {
 	action_binding: HashMap<Action, HashSet<KeyCode>>,
+	mouse_binding: HashMap<Action, HashSet<MouseButton>>,
 	key_state: HashMap<KeyCode, u32>,
 	mouse_state: MouseState
 }
@@ -28,16 +34,31 @@ impl InputState {
 impl Default for InputState {
 	fn default() -> Self {
 		let action_binding = HashMap::new();
+		let mouse_binding = HashMap::new();
 		let key_state = HashMap::new();
 		let mouse_state = MouseState::default();
 
-		let mut input_state = Self {action_binding, key_state, mouse_state};
+		let mut input_state = Self {action_binding, mouse_binding, key_state, mouse_state};
 
 		input_state.bind_action(Action::MoveForward, KeyCode::KeyW);
 		input_state.bind_action(Action::MoveBackward, KeyCode::KeyS);
 		input_state.bind_action(Action::StrafeLeft, KeyCode::KeyA);
 		input_state.bind_action(Action::StrafeRight, KeyCode::KeyD);
 		input_state.bind_action(Action::ToggleMinimap, KeyCode::Tab);
+		input_state.bind_action(Action::Use, KeyCode::KeyE);
+		input_state.bind_action(Action::Use, KeyCode::Space);
+		input_state.bind_action(Action::Fire, KeyCode::ControlLeft);
+		input_state.bind_mouse_action(Action::Fire, MouseButton::Left);
+		input_state.bind_action(Action::SelectWeapon(0), KeyCode::Digit1);
+		input_state.bind_action(Action::SelectWeapon(1), KeyCode::Digit2);
+		input_state.bind_action(Action::SelectWeapon(2), KeyCode::Digit3);
+		input_state.bind_action(Action::SelectWeapon(3), KeyCode::Digit4);
+		input_state.bind_action(Action::QuickSave, KeyCode::F5);
+		input_state.bind_action(Action::QuickLoad, KeyCode::F9);
+		input_state.bind_action(Action::SelectSaveSlot(0), KeyCode::F1);
+		input_state.bind_action(Action::SelectSaveSlot(1), KeyCode::F2);
+		input_state.bind_action(Action::SelectSaveSlot(2), KeyCode::F3);
+		input_state.bind_action(Action::SelectSaveSlot(3), KeyCode::F4);
 
 		input_state
 	}
@@ -60,6 +81,10 @@ impl InputState {
 		}
 	}
 
+	pub fn bind_mouse_action(&mut self, action: Action, button: MouseButton) {
+		self.mouse_binding.entry(action).or_default().insert(button);
+	}
+
 	// NOTE: works well but add '_' because not used now.
 	pub fn _unbind_action(&mut self, action: Action, key: KeyCode) {
 		match self.action_binding.get_mut(&action) {
@@ -84,6 +109,16 @@ impl InputState {
 	}
 
 	pub fn is_action_pressed(&mut self, action: Action) -> bool {
+		if let Some(buttons) = self.mouse_binding.get(&action) {
+			for button in buttons {
+				if let Some(state) = self.mouse_state.button_state.get_mut(button) {
+					*state &= Self::PRESSED;
+					if *state != 0 {
+						return true;
+					}
+				}
+			}
+		}
 		match self.action_binding.get(&action) {
 			None => false,
 			Some(keys) => {
@@ -102,6 +137,17 @@ impl InputState {
 	}
 
 	pub fn is_action_just_pressed(&mut self, action: Action) -> bool {
+		if let Some(buttons) = self.mouse_binding.get(&action) {
+			for button in buttons {
+				if let Some(state) = self.mouse_state.button_state.get_mut(button) {
+					let just_pressed = *state == Self::JUST | Self::PRESSED;
+					*state &= Self::PRESSED;
+					if just_pressed {
+						return true;
+					}
+				}
+			}
+		}
 		match self.action_binding.get(&action) {
 			None => false,
 			Some(keys) => {
@@ -139,10 +185,14 @@ impl InputState {
 		rel
 	}
 
+	pub fn set_mouse_button_state(&mut self, button: MouseButton, pressed: bool) {
+		let pressed_flag = if pressed { Self::PRESSED } else { 0b0 };
+		self.mouse_state.button_state.insert(button, Self::JUST | pressed_flag);
+	}
 	pub fn set_mouse_left_pressed(&mut self, pressed: bool) {
-		self.mouse_state.left_pressed = pressed;
+		self.set_mouse_button_state(MouseButton::Left, pressed);
 	}
 	pub fn set_mouse_right_pressed(&mut self, pressed: bool) {
-		self.mouse_state.right_pressed = pressed;
+		self.set_mouse_button_state(MouseButton::Right, pressed);
 	}
 }
\ No newline at end of file
@@ -32,16 +32,48 @@ Rendering: 화면 그리기. 게임장면+GUI. WebGPU, OpenGL, ...
 
 mod asset;
 mod webgpu;
-mod game;
+pub mod game; // No window or GPU needed. Can run headless(e.g. tests, servers and bots).
 mod input;
 mod minimap;
 mod firstperson;
 mod geometry;
+mod timestep;
 
 pub struct Rulf3D;
 
 impl Rulf3D {
 	pub fn testrun() -> Result<(), winit::error::EventLoopError> {
+		Self::testrun_with_tick_rate(60.0)
+	}
+
+	// tick_rate: game logic ticks per second. Rendering is not limited by it.
+	// The input is recorded and saved to DEMO_PATH on exit.
+	pub fn testrun_with_tick_rate(tick_rate: f64) -> Result<(), winit::error::EventLoopError> {
+		let map = match game::MapData::load("asset/test.map") {
+			Ok(map) => map,
+			Err(e) => {
+				println!("{}", e);
+				game::MapData::test_map()
+			}
+		};
+		Self::run(game::Demo::new(map, tick_rate.recip() as f32), false)
+	}
+
+	// Play the demo file back at its tick rate. The game input is ignored.
+	pub fn playdemo(path: impl AsRef<std::path::Path>) -> Result<(), winit::error::EventLoopError> {
+		match game::Demo::load(path) {
+			Ok(demo) => Self::run(demo, true),
+			Err(e) => {
+				println!("{}", e);
+				Ok(())
+			}
+		}
+	}
+
+	const DEMO_PATH: &str = "last.demo";
+
+	// playback: Play the inputs of the demo, otherwise record to it.
+	fn run(mut demo: game::Demo, playback: bool) -> Result<(), winit::error::EventLoopError> {
 		let event_loop = EventLoop::new().unwrap();
 		let window = Arc::new(Window::new(&event_loop).unwrap());
 		let mut webgpu = webgpu::WebGPU::new(window.clone());
@@ -50,12 +82,15 @@ impl Rulf3D {
 		let mut input_state = input::InputState::default();
 		let mut minimap_renderer = minimap::Renderer::new(&webgpu, &asset_server);
         let mut firstperson_renderer = firstperson::Renderer::new(&webgpu, &asset_server);
-		let mut game_world = game::GameWorld::test_gameworld();
+		let mut game_world = demo.start();
+		let mut demo_tick = 0;
+		let mut recording = !playback; // The demo can't continue from a loaded game.
+		let mut save_slot = 0;
 
         let mut draw_minimap = false;
 
-		let process_tickrate = Duration::from_secs_f64(60.0f64.recip());
-        let mut last_process_tick = Instant::now();
+		let mut timestep = timestep::FixedTimestep::new((demo.delta as f64).recip());
+        let mut last_frame = Instant::now();
         let mut focused = false;
 
         event_loop.run(
@@ -86,16 +121,26 @@ impl Rulf3D {
 						};
 					},
                     WindowEvent::RedrawRequested => {
+                        let alpha = timestep.alpha();
                         if draw_minimap {
-                            minimap_renderer.render(&webgpu, &game_world, &wgpu::Color{r:0.1, g:0.2, b:0.3, a:1.0});
+                            minimap_renderer.render(&webgpu, &game_world, alpha, &wgpu::Color{r:0.1, g:0.2, b:0.3, a:1.0});
                         }
                         else {
-                            firstperson_renderer.render(&webgpu, &game_world, &wgpu::Color{r:0.1, g:0.2, b:0.3, a:1.0});
+                            firstperson_renderer.render(&webgpu, &game_world, alpha, &wgpu::Color{r:0.1, g:0.2, b:0.3, a:1.0});
                         }
 					},
-                    WindowEvent::CloseRequested => elwt.exit(),
-                    WindowEvent::Resized(physical_size) if physical_size.width > 0 && physical_size.height > 0 
-					=> webgpu.reconfigure_surface_size(physical_size.width, physical_size.height),
+                    WindowEvent::CloseRequested => {
+                        if recording {
+                            if let Err(e) = demo.save(Self::DEMO_PATH) {
+                                println!("{}", e);
+                            }
+                        }
+                        elwt.exit();
+                    },
+                    WindowEvent::Resized(physical_size) if physical_size.width > 0 && physical_size.height > 0 => {
+                        webgpu.reconfigure_surface_size(physical_size.width, physical_size.height);
+                        window.request_redraw();
+                    },
                     _ => ()
                 },
                 Event::NewEvents(StartCause::Init) =>{
@@ -105,37 +150,76 @@ impl Rulf3D {
                     let _ = window.request_inner_size(winit::dpi::LogicalSize::new(1600, 1200));
                     window.set_outer_position(winit::dpi::LogicalPosition::new(400, 100));
                 }
-                Event::NewEvents(StartCause::Poll | StartCause::ResumeTimeReached { .. } | StartCause::WaitCancelled { .. }) =>
+                // Sleep until the next tick instead of polling. Input events wake it up earlier.
+                Event::AboutToWait =>
                 {
-                    let last_process_time = Instant::now().duration_since(last_process_tick);
-                    if last_process_time >= process_tickrate {
-                        let delta = last_process_time.as_secs_f64();
-                        last_process_tick = Instant::now();
-                        
-                        // input
-                        let dir_input_vec = input_state.get_dir_input_vector();
-						let wishdir = game_world.get_player_forward_vector().rotate((-glam::Vec2::Y).rotate(dir_input_vec));
-						game_world.translate_player(wishdir * 300.0 * delta as f32);
+                    let now = Instant::now();
+                    let elapsed = now.duration_since(last_frame).as_secs_f64();
+                    last_frame = now;
 
-						let mouse_rel_x = input_state.take_mouse_x_relative();
-						game_world.rotate_player(-mouse_rel_x.to_radians() * 100.0 * delta as f32);
+                    let ticks = timestep.advance(elapsed);
+                    for _ in 0..ticks {
+                        // input
+                        let player_input = if playback {
+                            demo.inputs.get(demo_tick).cloned().unwrap_or_default()
+                        }
+                        else {
+                            let player_input = game::PlayerInput {
+                                dir: input_state.get_dir_input_vector(),
+                                mouse_x: input_state.take_mouse_x_relative(),
+                                use_pressed: input_state.is_action_just_pressed(input::Action::Use),
+                                fire: input_state.is_action_pressed(input::Action::Fire),
+                                select_weapon: (0..game::WEAPONS.len()).find(|slot| input_state.is_action_just_pressed(input::Action::SelectWeapon(*slot)))
+                            };
+                            if recording {
+                                demo.record(&player_input);
+                            }
+                            player_input
+                        };
+                        demo_tick += 1;
+                        game_world.step(&player_input, demo.delta);
+                        //TODO: Show events on HUD and play sounds. Drained until then.
+                        game_world.take_events();
+
+                        // Restart the level after game over. The recording starts over too.
+                        if player_input.use_pressed && game_world.is_game_over() && !playback {
+                            recording = true;
+                            demo.inputs.clear();
+                            demo_tick = 0;
+                            game_world = demo.start();
+                        }
 
                         if input_state.is_action_just_pressed(input::Action::ToggleMinimap) {
                             draw_minimap = !draw_minimap;
                         }
-
-                        window.request_redraw();
                     }
-                },
-                Event::AboutToWait =>
-                {
-                    let last_process_time = Instant::now().duration_since(last_process_tick);
-                    if last_process_time >= process_tickrate {
-                        elwt.set_control_flow(ControlFlow::Poll);
+
+                    // save slots
+                    if !playback {
+                        if let Some(slot) = (0..game::SAVE_SLOTS).find(|slot| input_state.is_action_just_pressed(input::Action::SelectSaveSlot(*slot))) {
+                            save_slot = slot;
+                        }
+                        if input_state.is_action_just_pressed(input::Action::QuickSave) {
+                            if let Err(e) = game_world.save(game::save_slot_path(save_slot)) {
+                                eprintln!("{}", e);
+                            }
+                        }
+                        if input_state.is_action_just_pressed(input::Action::QuickLoad) {
+                            match game::GameWorld::load(game::save_slot_path(save_slot)) {
+                                Ok(loaded) => {
+                                    game_world = loaded;
+                                    recording = false;
+                                },
+                                Err(e) => eprintln!("{}", e)
+                            }
+                        }
                     }
-                    else {
-                        elwt.set_control_flow(ControlFlow::WaitUntil(Instant::now() + process_tickrate.mul_f64(0.5) - last_process_time)); 
+
+                    // Rendering is decoupled from the ticks and interpolates between them. Nothing to draw again without a tick.
+                    if ticks > 0 {
+                        window.request_redraw();
                     }
+                    elwt.set_control_flow(ControlFlow::WaitUntil(now + Duration::from_secs_f64(timestep.until_next_tick())));
                 },
                 _ => ()
             }
@@ -1,15 +1,18 @@
 use crate:: {
 	webgpu::{WebGPU, WebGPUDevice, WebGPUSurface},
-	game::GameWorld,
+	game::{GameWorld, EntityKind},
 	asset::AssetServer
 };
 
 mod wall;
 mod actor;
+mod rect;
 
 pub struct Renderer {
 	wall_render: wall::WallRender,
 	actor_render: actor::ActorRender,
+	door_render: rect::RectRender,
+	static_render: rect::RectRender,
 }
 
 impl Renderer {
@@ -17,34 +20,63 @@ impl Renderer {
 		Self { 
 			wall_render: wall::WallRender::new(webgpu, asset_server), 
 			actor_render: actor::ActorRender::new(webgpu, asset_server),
+			door_render: rect::RectRender::new(webgpu, asset_server),
+			static_render: rect::RectRender::new(webgpu, asset_server),
 		}
 	}
 }
 
 impl Renderer {
-	pub fn render(&mut self, webgpu: &WebGPU, game_world: &GameWorld, clear_color: &wgpu::Color) {
+	// alpha: interpolation between the last two ticks.
+	pub fn render(&mut self, webgpu: &WebGPU, game_world: &GameWorld, alpha: f32, clear_color: &wgpu::Color) {
 		// Convert game data to renderer specific
-		let cam_pos = glam::Mat4::from_translation(game_world.get_player_position().extend(0.0));
+		let player = game_world.get_interpolated_transform(game_world.get_player_id(), alpha).unwrap_or(game_world.get_player().transform);
+		let cam_pos = glam::Mat4::from_translation(player.position.extend(0.0));
 		let cam_rot = glam::Mat4::IDENTITY;//glam::Mat4::from_rotation_z(-std::f32::consts::FRAC_PI_2 + self.scene.get_player_angle());
 		let view = cam_rot.inverse() * cam_pos.inverse();
 		let proj = glam::Mat4::orthographic_lh(-400.0, 400.0, -300.0, 300.0, -0.001, 1.0001);
 		let viewproj = proj * view;
 		
-		// for wall rendering
-		let walls: Vec<u32> = game_world.get_walls().into_iter().flat_map(|(uvec, id)| 
-			{[uvec.x, uvec.y, id]}).collect();
 		let gridsize = game_world.get_grid_size();
 
+		// for door rendering
+		let door_rects: Vec<glam::Vec4> = game_world.get_doors().filter_map(|(coord, door)| {
+			let (min, max) = door.slab_rect()?;
+			let offset = (coord.as_vec2() + min) * gridsize;
+			let size = (max - min) * gridsize;
+			Some(glam::vec4(offset.x, offset.y, size.x, size.y))
+		}).collect();
+		let door_color = glam::vec4(0.8, 0.6, 0.1, 1.0);
+
+		// for static object rendering
+		let static_size = 20.0f32;
+		let static_rects: Vec<glam::Vec4> = game_world.get_entities().of_kind(EntityKind::Static).map(|(_, entity)|
+			(entity.transform.position - static_size * 0.5).extend(static_size).extend(static_size)).collect();
+		let static_color = glam::vec4(0.2, 0.6, 0.3, 1.0);
+
 		// for actors rendering
 		let actor_size = 50.0f32;
-		let actors_pos_ang = game_world.actors_position_angle_flatten();
+		let actors_pos_ang: Vec<[f32; 3]> = game_world.get_entities().iter()
+			.filter(|(_, entity)| matches!(entity.kind, EntityKind::Player | EntityKind::Enemy) && entity.is_alive())
+			.filter_map(|(id, _)| game_world.get_interpolated_transform(id, alpha))
+			.map(|transform| [transform.position.x, transform.position.y, transform.angle])
+			.collect();
 		let actor_color = glam::vec4(0.3, 0.2, 0.1, 1.0);
 
 		let (device, queue) = webgpu.get_device();
-		queue.write_buffer(&self.wall_render.instb, 0, bytemuck::cast_slice(walls.as_slice()));
+		self.wall_render.upload_tilemap(queue, game_world.get_tilemap());
 		queue.write_buffer(&self.wall_render.viewproj_ub, 0, bytemuck::cast_slice(&[viewproj]));
 		queue.write_buffer(&self.wall_render.gridsize_ub, 0, bytemuck::cast_slice(&[gridsize]));
-		self.wall_render.instb_len = walls.len() as u32 / 3;
+
+		queue.write_buffer(&self.door_render.viewproj_ub, 0, bytemuck::cast_slice(&[viewproj]));
+		queue.write_buffer(&self.door_render.color_ub, 0, bytemuck::cast_slice(&[door_color]));
+		queue.write_buffer(&self.door_render.instb, 0, bytemuck::cast_slice(door_rects.as_slice()));
+		self.door_render.instb_len = door_rects.len() as u32;
+
+		queue.write_buffer(&self.static_render.viewproj_ub, 0, bytemuck::cast_slice(&[viewproj]));
+		queue.write_buffer(&self.static_render.color_ub, 0, bytemuck::cast_slice(&[static_color]));
+		queue.write_buffer(&self.static_render.instb, 0, bytemuck::cast_slice(static_rects.as_slice()));
+		self.static_render.instb_len = static_rects.len() as u32;
 
 		queue.write_buffer(&self.actor_render.viewproj_ub, 0, bytemuck::cast_slice(&[viewproj]));
 		queue.write_buffer(&self.actor_render.actorsize_ub, 0, bytemuck::cast_slice(&[actor_size]));
@@ -76,6 +108,18 @@ impl Renderer {
 		render_pass.set_vertex_buffer(1, self.wall_render.instb.slice(..));
 		render_pass.draw(0..4, 0..self.wall_render.instb_len);
 
+		render_pass.set_pipeline(&self.door_render.pipeline);
+		render_pass.set_bind_group(0, &self.door_render.bind_group, &[]);
+		render_pass.set_vertex_buffer(0, self.door_render.vb.slice(..));
+		render_pass.set_vertex_buffer(1, self.door_render.instb.slice(..));
+		render_pass.draw(0..4, 0..self.door_render.instb_len);
+
+		render_pass.set_pipeline(&self.static_render.pipeline);
+		render_pass.set_bind_group(0, &self.static_render.bind_group, &[]);
+		render_pass.set_vertex_buffer(0, self.static_render.vb.slice(..));
+		render_pass.set_vertex_buffer(1, self.static_render.instb.slice(..));
+		render_pass.draw(0..4, 0..self.static_render.instb_len);
+
 		render_pass.set_pipeline(&self.actor_render.pipeline);
 		render_pass.set_bind_group(0, &self.actor_render.bind_group, &[]);
 		render_pass.set_vertex_buffer(0, self.actor_render.vb.slice(..));
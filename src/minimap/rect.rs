@@ -0,0 +1,153 @@
+use wgpu::util::DeviceExt;
+use crate:: {
+	webgpu::{WebGPU, WebGPUDevice, WebGPUConfig},
+	asset::AssetServer,
+	geometry::{Vertex, QUAD_VERT}
+};
+
+pub struct RectRender {
+	pub vb: wgpu::Buffer,
+	pub instb: wgpu::Buffer,
+	pub instb_len: u32,
+	pub viewproj_ub: wgpu::Buffer,
+	pub color_ub: wgpu::Buffer,
+
+	pub bind_group: wgpu::BindGroup,
+	pub pipeline: wgpu::RenderPipeline
+}
+
+impl RectRender {
+	const MAX_RECT_INSTANCE: u64 = 4096;
+	pub fn new(webgpu: &WebGPU, asset_server: &AssetServer) -> Self {
+		let (device, _) = webgpu.get_device();
+		let vb = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+			label: Some("RectRender::vb"),
+			contents: bytemuck::cast_slice(&QUAD_VERT),
+			usage: wgpu::BufferUsages::VERTEX
+		});
+
+		let instb = device.create_buffer(&wgpu::BufferDescriptor { // rect: [f32;4]
+			label: Some("RectRender::instb"),
+			size: std::mem::size_of::<glam::Vec4>() as u64 * Self::MAX_RECT_INSTANCE,
+			usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false
+		});
+
+		let viewproj_ub = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("RectRender::viewproj_ub"),
+			size: std::mem::size_of::<glam::Mat4>() as u64,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false
+		});
+
+		let color_ub = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("RectRender::color_ub"),
+			size: std::mem::size_of::<glam::Vec4>() as u64,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false
+		});
+
+		let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("RectRender bind group layout"),
+			entries: &[
+				wgpu::BindGroupLayoutEntry { //view projection mat4x4
+					binding: 0,
+					visibility: wgpu::ShaderStages::VERTEX,
+					ty: wgpu::BindingType::Buffer {
+						ty: wgpu::BufferBindingType::Uniform,
+						has_dynamic_offset: false,
+						min_binding_size: None
+					},
+					count: None
+				},
+				wgpu::BindGroupLayoutEntry { //color
+					binding: 1,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Buffer {
+						ty: wgpu::BufferBindingType::Uniform,
+						has_dynamic_offset: false,
+						min_binding_size: None
+					},
+					count: None
+				}
+			]
+		});
+
+		let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: Some("RectRender::bind_group"),
+			layout: &bind_group_layout,
+			entries: &[
+				wgpu::BindGroupEntry {
+					binding: 0,
+					resource: viewproj_ub.as_entire_binding()
+				},
+				wgpu::BindGroupEntry {
+					binding: 1,
+					resource: color_ub.as_entire_binding()
+				}
+			]
+		});
+
+		let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("RectRender pipeline layout"),
+			bind_group_layouts: &[&bind_group_layout],
+			push_constant_ranges: &[]
+		});
+
+		let shader_module = asset_server.get_shader("minimap_rect").unwrap();
+		let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("RectRender::render_pipeline"),
+			layout: Some(&pipeline_layout),
+			vertex: wgpu::VertexState {
+				module: shader_module,
+				entry_point: "vs_main",
+				buffers: &[
+					wgpu::VertexBufferLayout {
+						array_stride: std::mem::size_of::<Vertex>() as u64,
+						step_mode: wgpu::VertexStepMode::Vertex,
+						attributes: &Vertex::VERT_ATTR
+					},
+					wgpu::VertexBufferLayout {
+						array_stride: std::mem::size_of::<glam::Vec4>() as u64,
+						step_mode: wgpu::VertexStepMode::Instance,
+						attributes: &[
+							wgpu::VertexAttribute {
+								format: wgpu::VertexFormat::Float32x4,
+								offset: 0,
+								shader_location: 3
+							}
+						]
+					}
+				],
+				compilation_options: wgpu::PipelineCompilationOptions::default()
+			},
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleStrip,
+				strip_index_format: None,
+				front_face: wgpu::FrontFace::Ccw,
+				cull_mode: Some(wgpu::Face::Back),
+				unclipped_depth: false,
+				polygon_mode: wgpu::PolygonMode::Fill,
+				conservative: false
+			},
+			depth_stencil: None,
+			multisample: wgpu::MultisampleState::default(),
+			fragment: Some(wgpu::FragmentState {
+				module: shader_module,
+				entry_point: "fs_main",
+				targets: &[Some(wgpu::ColorTargetState {
+					format: webgpu.get_config().format,
+					blend: Some(wgpu::BlendState::REPLACE),
+					write_mask: wgpu::ColorWrites::ALL
+				})],
+				compilation_options: wgpu::PipelineCompilationOptions::default()
+			}),
+			multiview: None,
+			cache: None
+		});
+
+		Self {
+			vb, instb, instb_len: 0, viewproj_ub, color_ub, bind_group, pipeline: render_pipeline
+		}
+	}
+}
@@ -2,13 +2,15 @@ use wgpu::util::DeviceExt;
 use crate:: {
 	webgpu::{WebGPU, WebGPUDevice, WebGPUConfig},
 	asset::AssetServer,
-	geometry::{Vertex, QUAD_VERT}
+	geometry::{Vertex, QUAD_VERT},
+	game::{TileMap, TileType, WallFaces, TileDirty, TileRect}
 };
 
 pub struct WallRender {
 	pub vb: wgpu::Buffer,
 	pub instb: wgpu::Buffer,
 	pub instb_len: u32,
+	tilemap_revision: u64, // of the uploaded tiles. 0: nothing uploaded yet
 	pub viewproj_ub: wgpu::Buffer,
 	pub gridsize_ub: wgpu::Buffer,
 	_texture_array_view: wgpu::TextureView,
@@ -28,9 +30,10 @@ impl WallRender {
 			usage: wgpu::BufferUsages::VERTEX
 		});
 
-		let instb = device.create_buffer(&wgpu::BufferDescriptor {// offset: [u32;2], texid: u32
+		// One instance per tile. offset: [u32;2], texid: u32(u32::MAX: not a wall), lock: u32(0: not locked, key id + 1)
+		let instb = device.create_buffer(&wgpu::BufferDescriptor {
 			label: Some("WallRender::instb"),
-			size: Self::MAX_WALL_INSTANCE * std::mem::size_of::<u32>() as u64 * 3,
+			size: Self::MAX_WALL_INSTANCE * std::mem::size_of::<u32>() as u64 * 4,
 			usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
 			mapped_at_creation: false
 		});
@@ -143,7 +146,7 @@ impl WallRender {
 						attributes: &Vertex::VERT_ATTR
 					},
 					wgpu::VertexBufferLayout {
-						array_stride: std::mem::size_of::<[u32; 2]>() as u64 + std::mem::size_of::<u32>() as u64,
+						array_stride: std::mem::size_of::<[u32; 2]>() as u64 + std::mem::size_of::<u32>() as u64 * 2,
 						step_mode: wgpu::VertexStepMode::Instance,
 						attributes: &[
 							wgpu::VertexAttribute {
@@ -155,6 +158,11 @@ impl WallRender {
 								format: wgpu::VertexFormat::Uint32,
 								offset: std::mem::size_of::<[u32;2]>() as u64,
 								shader_location: 4
+							},
+							wgpu::VertexAttribute {
+								format: wgpu::VertexFormat::Uint32,
+								offset: std::mem::size_of::<[u32;3]>() as u64,
+								shader_location: 5
 							}
 						]
 					}
@@ -187,8 +195,38 @@ impl WallRender {
 		});
 
 		Self {
-			vb, instb, instb_len: 0, viewproj_ub, gridsize_ub, bind_group, pipeline: render_pipeline,
+			vb, instb, instb_len: 0, tilemap_revision: 0, viewproj_ub, gridsize_ub, bind_group, pipeline: render_pipeline,
 			_texture_array_view: texture_array_view, _texture_sampler: texture_sampler
 		}
 	}
-}
\ No newline at end of file
+
+	// Upload only the tiles changed after the last upload.
+	pub fn upload_tilemap(&mut self, queue: &wgpu::Queue, tilemap: &TileMap) {
+		let rect = match tilemap.get_dirty_since(self.tilemap_revision) {
+			TileDirty::Clean => return,
+			TileDirty::Tiles(rect) => rect,
+			TileDirty::All => TileRect { min: glam::UVec2::ZERO, max: glam::uvec2(tilemap.width, tilemap.height).saturating_sub(glam::UVec2::ONE) }
+		};
+		self.tilemap_revision = tilemap.get_revision();
+		let len = (tilemap.get_data().len() as u64).min(Self::MAX_WALL_INSTANCE);
+		self.instb_len = len as u32;
+
+		for y in rect.min.y..=rect.max.y {
+			let start = (y * tilemap.width + rect.min.x) as u64;
+			let end = ((y * tilemap.width + rect.max.x) as u64 + 1).min(len);
+			if start >= end {
+				break;
+			}
+			let instances: Vec<[u32; 4]> = (start..end).map(|i| {
+				let (x, y) = (i as u32 % tilemap.width, i as u32 / tilemap.width);
+				let (texid, lock) = match tilemap.get_data()[i as usize] {
+					TileType::Wall(WallFaces { north: id, .. }) | TileType::PushWall(id) => (id, 0),
+					TileType::Gate(id, key) => (id, key + 1),
+					TileType::Empty(_, _) | TileType::Door(_, _) => (u32::MAX, 0)
+				};
+				[x, y, texid, lock]
+			}).collect();
+			queue.write_buffer(&self.instb, start * std::mem::size_of::<[u32; 4]>() as u64, bytemuck::cast_slice(&instances));
+		}
+	}
+}
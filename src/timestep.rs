@@ -0,0 +1,51 @@
+// Fixed step accumulator. The game logic runs in fixed ticks regardless of the frame rate,
+// and the renderer interpolates between the last two ticks by alpha().
+
+pub struct FixedTimestep {
+	step: f64, // seconds per tick
+	accumulator: f64
+}
+
+impl FixedTimestep {
+	// Ticks to run at most per advance(). Drops the time after a long stall(e.g. dragging the window) instead of catching up.
+	const MAX_TICKS: u32 = 10;
+
+	pub fn new(tick_rate: f64) -> Self {
+		Self { step: tick_rate.recip(), accumulator: 0.0 }
+	}
+
+	// Accumulate the elapsed time(seconds) and returns the number of ticks to run.
+	pub fn advance(&mut self, elapsed: f64) -> u32 {
+		self.accumulator += elapsed;
+		let ticks = (self.accumulator / self.step).floor() as u32;
+		self.accumulator -= ticks as f64 * self.step;
+		if ticks > Self::MAX_TICKS {
+			self.accumulator = 0.0;
+			return Self::MAX_TICKS;
+		}
+		ticks
+	}
+
+	// Progress to the next tick in [0, 1).
+	pub fn alpha(&self) -> f32 {
+		(self.accumulator / self.step) as f32
+	}
+
+	// Seconds left to the next tick.
+	pub fn until_next_tick(&self) -> f64 {
+		(self.step - self.accumulator).max(0.0)
+	}
+}
+
+
+#[test]
+fn fixed_timestep_accumulates() {
+	let mut timestep = FixedTimestep::new(50.0);
+	assert_eq!(timestep.advance(0.01), 0);
+	assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+	assert_eq!(timestep.advance(0.035), 2);
+	assert!((timestep.alpha() - 0.25).abs() < 1e-4);
+	assert!((timestep.until_next_tick() - 0.015).abs() < 1e-6);
+	assert_eq!(timestep.advance(10.0), FixedTimestep::MAX_TICKS);
+	assert_eq!(timestep.alpha(), 0.0);
+}